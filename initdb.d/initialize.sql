@@ -6,4 +6,61 @@ create table todos (
     created_at datetime
 );
 
+create table invoices (
+    id BIGINT UNSIGNED PRIMARY KEY NOT NULL AUTO_INCREMENT,
+    amount DECIMAL(12, 2) NOT NULL
+);
+
+create table projects (
+    id BIGINT UNSIGNED PRIMARY KEY NOT NULL AUTO_INCREMENT,
+    name TEXT NOT NULL
+);
+
+create table tasks (
+    project_id BIGINT UNSIGNED NOT NULL,
+    number INT NOT NULL,
+    title TEXT NOT NULL,
+    PRIMARY KEY (project_id, number),
+    FOREIGN KEY (project_id) REFERENCES projects (id)
+);
+
+create table notes (
+    id BIGINT UNSIGNED PRIMARY KEY NOT NULL AUTO_INCREMENT,
+    body TEXT NOT NULL
+);
+
+create table tags (
+    id BIGINT UNSIGNED PRIMARY KEY NOT NULL AUTO_INCREMENT,
+    name TEXT NOT NULL
+);
+
+create table dumps (
+    id BIGINT UNSIGNED PRIMARY KEY NOT NULL AUTO_INCREMENT,
+    amount DECIMAL(12, 2),
+    ratio DOUBLE,
+    done BOOLEAN,
+    created_at DATETIME,
+    due_on DATE,
+    payload JSON,
+    note TEXT
+);
+
+create table typed_values (
+    id BIGINT UNSIGNED PRIMARY KEY NOT NULL AUTO_INCREMENT,
+    label VARCHAR(32),
+    level SMALLINT,
+    due_on DATE,
+    starts_at TIME,
+    payload JSON,
+    data BLOB,
+    priority ENUM('low', 'high')
+);
+
+create database if not exists billing;
+
+create table billing.invoices (
+    id BIGINT UNSIGNED PRIMARY KEY NOT NULL AUTO_INCREMENT,
+    amount DECIMAL(12, 2) NOT NULL
+);
+
 create database if not exists fizz;
@@ -0,0 +1,69 @@
+create table todos (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    description TEXT NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT FALSE,
+    progress REAL,
+    created_at TIMESTAMP
+);
+
+create table invoices (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    amount NUMERIC(12, 2) NOT NULL
+);
+
+create table projects (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL
+);
+
+create table tasks (
+    project_id BIGINT NOT NULL,
+    number INT NOT NULL,
+    title TEXT NOT NULL,
+    PRIMARY KEY (project_id, number),
+    FOREIGN KEY (project_id) REFERENCES projects (id)
+);
+
+create table notes (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    body TEXT NOT NULL
+);
+
+create table tags (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL
+);
+
+create table dumps (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    amount NUMERIC(12, 2),
+    ratio DOUBLE PRECISION,
+    done BOOLEAN,
+    created_at TIMESTAMP,
+    due_on DATE,
+    token UUID,
+    payload JSONB,
+    note TEXT
+);
+
+create type priority as enum ('low', 'high');
+
+create table typed_values (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    label VARCHAR(32),
+    level SMALLINT,
+    due_on DATE,
+    starts_at TIME,
+    payload JSON,
+    data BYTEA,
+    priority priority
+);
+
+create schema billing;
+
+create table billing.invoices (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    amount NUMERIC(12, 2) NOT NULL
+);
+
+create database fizz;
@@ -0,0 +1,318 @@
+use chrono::Utc;
+use sqlx::{Connect, Connection, Database, MySqlPool, PgPool, SqlitePool};
+use structopt::StructOpt;
+use testfixtures::{Cleanup, Dumper, Loader, MySqlLoader, PostgresLoader, SqliteLoader};
+
+/// Load, validate and dump testfixtures fixture files.
+#[derive(StructOpt)]
+#[structopt(name = "testfixtures")]
+enum Command {
+    /// Load fixture files into a database.
+    Load {
+        #[structopt(flatten)]
+        fixtures: Fixtures,
+
+        #[structopt(flatten)]
+        check: TestDatabaseCheck,
+
+        /// Clean all tables, not only the ones in fixture files.
+        #[structopt(long)]
+        clean_all_tables: bool,
+
+        /// How to clean tables: delete, truncate, delete-where-fixture-keys or none.
+        #[structopt(long, default_value = "delete", parse(try_from_str = parse_cleanup))]
+        cleanup: Cleanup,
+
+        /// The schema to qualify table names with.
+        #[structopt(long)]
+        schema: Option<String>,
+    },
+    /// Parse fixture files and check them against the schema without changing the database.
+    Validate {
+        #[structopt(flatten)]
+        fixtures: Fixtures,
+    },
+    /// Write rows of tables into `<table>.yml` fixture files.
+    Dump {
+        /// A database URL, like mysql://, postgres:// or sqlite:.
+        #[structopt(long, env = "DATABASE_URL")]
+        url: String,
+
+        /// The directory to write fixture files into.
+        #[structopt(short, long, default_value = ".")]
+        directory: String,
+
+        /// Tables to dump. A table can be qualified by a schema, like billing.invoices.
+        #[structopt(required = true)]
+        tables: Vec<String>,
+    },
+}
+
+#[derive(StructOpt)]
+struct Fixtures {
+    /// A database URL, like mysql://, postgres:// or sqlite:.
+    #[structopt(long, env = "DATABASE_URL")]
+    url: String,
+
+    /// Fixture files or directories.
+    #[structopt(required = true)]
+    paths: Vec<String>,
+}
+
+/// Options of the test database check. Every rule that is set has to pass.
+#[derive(StructOpt)]
+struct TestDatabaseCheck {
+    /// Skip the check that the database is a test database.
+    #[structopt(long)]
+    skip_test_database_check: bool,
+
+    /// Check that the database name matches this regex instead of ending with "test".
+    #[structopt(long)]
+    test_database_pattern: Option<String>,
+
+    /// Check that the database name is one of these names instead of ending with "test". Can be repeated.
+    #[structopt(long = "allow-database", number_of_values = 1)]
+    allowed_databases: Vec<String>,
+
+    /// Check that the database server is on localhost or a private address.
+    #[structopt(long)]
+    require_local_host: bool,
+
+    /// Check that this environment variable is set. Can be repeated.
+    #[structopt(long = "require-env", number_of_values = 1)]
+    required_env_vars: Vec<String>,
+}
+
+/// Options of the load subcommand.
+struct LoadOptions {
+    check: TestDatabaseCheck,
+    clean_all_tables: bool,
+    cleanup: Cleanup,
+    schema: Option<String>,
+}
+
+enum Backend {
+    MySql,
+    Postgres,
+    Sqlite,
+}
+
+fn backend(url: &str) -> anyhow::Result<Backend> {
+    match url.split(':').next() {
+        Some("mysql") | Some("mariadb") => Ok(Backend::MySql),
+        Some("postgres") | Some("postgresql") => Ok(Backend::Postgres),
+        Some("sqlite") => Ok(Backend::Sqlite),
+        _ => anyhow::bail!("unsupported database url: {}", url),
+    }
+}
+
+fn parse_cleanup(s: &str) -> Result<Cleanup, String> {
+    match s {
+        "delete" => Ok(Cleanup::Delete),
+        "truncate" => Ok(Cleanup::Truncate),
+        "delete-where-fixture-keys" => Ok(Cleanup::DeleteWhereFixtureKeys),
+        "none" => Ok(Cleanup::None),
+        _ => Err(format!("unknown cleanup strategy: {}", s)),
+    }
+}
+
+/// Set options shared by all backends.
+fn configure<D, C>(
+    cfg: &mut Loader<D, C, Utc, Utc>,
+    paths: &[String],
+    options: Option<&LoadOptions>,
+) where
+    D: Database + Sync + Send,
+    C: Connection<Database = D> + Connect<Database = D> + Send,
+{
+    cfg.location(Utc);
+    cfg.paths(paths.iter().map(String::as_str).collect());
+    match options {
+        Some(options) => {
+            let check = &options.check;
+            if check.skip_test_database_check {
+                cfg.skip_test_database_check();
+            }
+            if let Some(pattern) = &check.test_database_pattern {
+                cfg.test_database_pattern(pattern);
+            }
+            if !check.allowed_databases.is_empty() {
+                cfg.allow_databases(check.allowed_databases.iter().map(String::as_str).collect());
+            }
+            if check.require_local_host {
+                cfg.require_local_host();
+            }
+            for name in &check.required_env_vars {
+                cfg.require_env(name);
+            }
+            if options.clean_all_tables {
+                cfg.clean_all_tables();
+            }
+            cfg.cleanup(options.cleanup);
+            if let Some(schema) = &options.schema {
+                cfg.schema(schema);
+            }
+        }
+        // Validate does not load fixtures, so the database does not have to be a test database.
+        None => cfg.skip_test_database_check(),
+    }
+}
+
+/// Create a loader for the backend of `fixtures.url`, and load the fixtures if `options` is given.
+/// Returns the number of fixture files.
+async fn run(fixtures: &Fixtures, options: Option<&LoadOptions>) -> anyhow::Result<usize> {
+    let paths = &fixtures.paths;
+    let count = match backend(&fixtures.url)? {
+        Backend::MySql => {
+            let pool = MySqlPool::new(&fixtures.url).await?;
+            let loader = MySqlLoader::new(|cfg| {
+                cfg.database(pool);
+                configure(cfg, paths, options);
+            })
+            .await?;
+            if options.is_some() {
+                loader.load().await?;
+            }
+            loader.fixture_files.len()
+        }
+        Backend::Postgres => {
+            let pool = PgPool::new(&fixtures.url).await?;
+            let loader = PostgresLoader::new(|cfg| {
+                cfg.database(pool);
+                configure(cfg, paths, options);
+            })
+            .await?;
+            if options.is_some() {
+                loader.load().await?;
+            }
+            loader.fixture_files.len()
+        }
+        Backend::Sqlite => {
+            let pool = SqlitePool::new(&fixtures.url).await?;
+            let loader = SqliteLoader::new(|cfg| {
+                cfg.database(pool);
+                configure(cfg, paths, options);
+            })
+            .await?;
+            if options.is_some() {
+                loader.load().await?;
+            }
+            loader.fixture_files.len()
+        }
+    };
+    Ok(count)
+}
+
+#[async_std::main]
+async fn main() -> anyhow::Result<()> {
+    match Command::from_args() {
+        Command::Load {
+            fixtures,
+            check,
+            clean_all_tables,
+            cleanup,
+            schema,
+        } => {
+            let options = LoadOptions {
+                check,
+                clean_all_tables,
+                cleanup,
+                schema,
+            };
+            let count = run(&fixtures, Some(&options)).await?;
+            println!("loaded {} fixture files", count);
+        }
+        Command::Validate { fixtures } => {
+            let count = run(&fixtures, None).await?;
+            println!("{} fixture files are valid", count);
+        }
+        Command::Dump {
+            url,
+            directory,
+            tables,
+        } => {
+            let count = tables.len();
+            let tables = tables.iter().map(String::as_str).collect();
+            match backend(&url)? {
+                Backend::MySql => {
+                    Dumper::dump(&MySqlPool::new(&url).await?, tables, &directory).await?
+                }
+                Backend::Postgres => {
+                    Dumper::dump(&PgPool::new(&url).await?, tables, &directory).await?
+                }
+                Backend::Sqlite => {
+                    Dumper::dump(&SqlitePool::new(&url).await?, tables, &directory).await?
+                }
+            }
+            println!("dumped {} tables into {}", count, directory);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{backend, parse_cleanup, Backend, Command};
+    use structopt::StructOpt;
+    use testfixtures::Cleanup;
+
+    #[test]
+    fn test_backend() {
+        assert!(matches!(
+            backend("mysql://root@localhost/test"),
+            Ok(Backend::MySql)
+        ));
+        assert!(matches!(
+            backend("postgres://postgres@localhost/test"),
+            Ok(Backend::Postgres)
+        ));
+        assert!(matches!(backend("sqlite:test.db"), Ok(Backend::Sqlite)));
+        assert!(backend("mssql://localhost/test").is_err());
+    }
+
+    #[test]
+    fn test_parse_cleanup() {
+        assert_eq!(parse_cleanup("truncate"), Ok(Cleanup::Truncate));
+        assert_eq!(
+            parse_cleanup("delete-where-fixture-keys"),
+            Ok(Cleanup::DeleteWhereFixtureKeys)
+        );
+        assert!(parse_cleanup("drop").is_err());
+    }
+
+    #[test]
+    fn test_test_database_check() {
+        let command = Command::from_iter_safe(vec![
+            "testfixtures",
+            "load",
+            "--url",
+            "postgres://postgres@localhost/app_ci",
+            "--test-database-pattern",
+            "^app_ci",
+            "--allow-database",
+            "app_ci",
+            "--allow-database",
+            "app_ci_2",
+            "--require-local-host",
+            "--require-env",
+            "CI",
+            "fixtures",
+        ]);
+        match command {
+            Ok(Command::Load {
+                fixtures, check, ..
+            }) => {
+                assert_eq!(fixtures.paths, vec!["fixtures".to_string()]);
+                assert!(!check.skip_test_database_check);
+                assert_eq!(check.test_database_pattern, Some("^app_ci".to_string()));
+                assert_eq!(
+                    check.allowed_databases,
+                    vec!["app_ci".to_string(), "app_ci_2".to_string()]
+                );
+                assert!(check.require_local_host);
+                assert_eq!(check.required_env_vars, vec!["CI".to_string()]);
+            }
+            _ => panic!("expected a load command"),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+/// How tables are cleaned before fixtures are inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Cleanup {
+    /// Delete all rows of the tables. This is the default.
+    #[default]
+    Delete,
+    /// Truncate the tables, which also resets auto increment counters.
+    /// MySQL commits the transaction implicitly when a table is truncated,
+    /// so a failed load can not roll back the cleanup.
+    /// SQLite has no TRUNCATE and deletes all rows instead.
+    Truncate,
+    /// Delete only rows whose primary keys appear in the fixtures.
+    /// Records without a primary key value, or with a `RAW=` one, delete nothing.
+    DeleteWhereFixtureKeys,
+    /// Do not clean the tables, so fixtures are appended to existing rows.
+    None,
+}
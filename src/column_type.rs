@@ -0,0 +1,175 @@
+use yaml_rust::Yaml;
+
+/// The type of a column, read from the schema by [init](crate::Database::init).
+/// Fixture values are converted to the type of their column before they are bound.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnType {
+    /// An integer column which holds values from `min` to `max`.
+    Integer {
+        min: i128,
+        max: i128,
+    },
+    /// An exact numeric column.
+    Decimal,
+    /// A floating-point column.
+    Float,
+    Boolean,
+    Date,
+    /// A time of day.
+    Time,
+    /// A timestamp, with or without a time zone.
+    Datetime,
+    Json,
+    /// A binary column. Strings are stored as their UTF-8 bytes.
+    Binary,
+    /// An enum column, with its values.
+    Enum(Vec<String>),
+    /// A text column. Strings are stored as they are, even if they look like dates.
+    Text,
+    /// Any other type. Values are bound as their YAML types say.
+    Other,
+}
+
+impl ColumnType {
+    /// An integer column of `bits` bits.
+    pub(crate) fn signed(bits: u32) -> ColumnType {
+        ColumnType::Integer {
+            min: -(1 << (bits - 1)),
+            max: (1 << (bits - 1)) - 1,
+        }
+    }
+
+    /// An unsigned integer column of `bits` bits.
+    pub(crate) fn unsigned(bits: u32) -> ColumnType {
+        ColumnType::Integer {
+            min: 0,
+            max: (1 << bits) - 1,
+        }
+    }
+}
+
+/// A column of a table, read from the schema by [init](crate::Database::init).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+    /// Whether the column takes NULL.
+    pub nullable: bool,
+    /// Whether the column gets a value when a record leaves it out,
+    /// from a default, a sequence or auto increment.
+    pub has_default: bool,
+    /// The maximum number of characters of a string column.
+    pub max_length: Option<usize>,
+}
+
+/// The text of a scalar YAML value, or `None` for a collection.
+pub(crate) fn yaml_text(value: &Yaml) -> Option<String> {
+    match value {
+        Yaml::String(v) | Yaml::Real(v) => Some(v.clone()),
+        Yaml::Integer(v) => Some(v.to_string()),
+        Yaml::Boolean(v) => Some(v.to_string()),
+        Yaml::Null => Some("null".to_string()),
+        _ => None,
+    }
+}
+
+/// Serialize a YAML value as JSON.
+/// Returns `None` for values which JSON can not represent, like `.inf`.
+pub(crate) fn yaml_to_json(value: &Yaml) -> Option<String> {
+    match value {
+        Yaml::Null => Some("null".to_string()),
+        Yaml::Boolean(v) => Some(v.to_string()),
+        Yaml::Integer(v) => Some(v.to_string()),
+        Yaml::Real(_) => value
+            .as_f64()
+            .filter(|x| x.is_finite())
+            .map(|x| x.to_string()),
+        Yaml::String(v) => Some(json_string(v)),
+        Yaml::Array(values) => {
+            let values = values
+                .iter()
+                .map(yaml_to_json)
+                .collect::<Option<Vec<String>>>()?;
+            Some(format!("[{}]", values.join(",")))
+        }
+        Yaml::Hash(hash) => {
+            let entries = hash
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Yaml::Array(_) | Yaml::Hash(_) => return None,
+                        key => yaml_text(key)?,
+                    };
+                    Some(format!("{}:{}", json_string(&key), yaml_to_json(value)?))
+                })
+                .collect::<Option<Vec<String>>>()?;
+            Some(format!("{{{}}}", entries.join(",")))
+        }
+        _ => None,
+    }
+}
+
+/// Quote a string for JSON.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::column_type::{yaml_to_json, ColumnType};
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_integer_ranges() {
+        assert_eq!(
+            ColumnType::signed(16),
+            ColumnType::Integer {
+                min: -32768,
+                max: 32767
+            }
+        );
+        assert_eq!(
+            ColumnType::unsigned(64),
+            ColumnType::Integer {
+                min: 0,
+                max: u64::MAX as i128
+            }
+        );
+    }
+
+    #[test]
+    fn test_yaml_to_json() -> anyhow::Result<()> {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+            name: "fizz \"buzz\""
+            tags: [1, 2.5, true, ~]
+            nested:
+              10: ok
+            "#,
+        )?;
+        assert_eq!(
+            yaml_to_json(&yaml[0]),
+            Some(
+                r#"{"name":"fizz \"buzz\"","tags":[1,2.5,true,null],"nested":{"10":"ok"}}"#
+                    .to_string()
+            )
+        );
+        let yaml = YamlLoader::load_from_str("[.inf]")?;
+        assert_eq!(yaml_to_json(&yaml[0]), None);
+        Ok(())
+    }
+}
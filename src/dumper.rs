@@ -0,0 +1,63 @@
+use crate::error::Error;
+use async_trait::async_trait;
+use std::fs;
+use std::path::Path;
+use yaml_rust::{Yaml, YamlEmitter};
+
+/// Represents a database pool which can read rows of a table as fixture records.
+#[async_trait]
+pub trait Dump {
+    /// Read all rows of `table` as records, which are hashes from column names to values.
+    /// Values are in the form [Loader](crate::loader::Loader) reads back.
+    async fn records(&self, table: &str) -> Result<Vec<Yaml>, Error>;
+}
+
+/// This type writes rows of tables into fixture files.
+pub struct Dumper;
+
+impl Dumper {
+    /// Write all rows of each table in `tables` into `<table>.yml` in `directory`.
+    /// A table can be qualified by a schema, like `billing.invoices`.
+    pub async fn dump<P>(pool: &P, tables: Vec<&str>, directory: &str) -> Result<(), Error>
+    where
+        P: Dump + Sync,
+    {
+        for table in tables {
+            let records = Yaml::Array(pool.records(table).await?);
+            let mut content = String::new();
+            // Writing to a String does not fail.
+            YamlEmitter::new(&mut content).dump(&records).unwrap();
+            content.push('\n');
+            let path = Path::new(directory).join(format!("{}.yml", table));
+            if let Err(err) = fs::write(&path, content) {
+                return Err(Error::Write {
+                    path: path.to_string_lossy().to_string(),
+                    source: err,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a table name into its schema, if it is qualified, and the bare name.
+pub(crate) fn split_table(table: &str) -> (Option<&str>, &str) {
+    match table.split_once('.') {
+        Some((schema, name)) => (Some(schema), name),
+        None => (None, table),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dumper::split_table;
+
+    #[test]
+    fn test_split_table() {
+        assert_eq!(split_table("todos"), (None, "todos"));
+        assert_eq!(
+            split_table("billing.invoices"),
+            (Some("billing"), "invoices")
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use yaml_rust::ScanError;
+
+/// Errors returned by testfixtures.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// [location](crate::loader::Loader::location) option is not set.
+    #[error("testfixtures: you need a location")]
+    MissingLocation,
+
+    /// [database](crate::loader::Loader::database) option is not set.
+    #[error("testfixtures: you need a pool")]
+    MissingPool,
+
+    /// The database does not pass the test database check. `rule` says which check failed.
+    #[error("testfixtures: '{name}' does not appear to be a test database: {rule}")]
+    NotATestDatabase { name: String, rule: String },
+
+    /// The [test_database_pattern](crate::loader::Loader::test_database_pattern) option is not a valid regex.
+    #[error("testfixtures: invalid test database pattern '{pattern}': {source}")]
+    InvalidPattern {
+        pattern: String,
+        source: regex::Error,
+    },
+
+    /// An option can not be used with the database.
+    #[error("testfixtures: {option} is not supported on {database}")]
+    Unsupported { option: String, database: String },
+
+    /// A fixture file or directory can not be read.
+    #[error("testfixtures: failed to read {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    /// A dumped fixture file can not be written.
+    #[error("testfixtures: failed to write {path}: {source}")]
+    Write {
+        path: String,
+        source: std::io::Error,
+    },
+
+    /// A fixture file name is not valid UTF-8.
+    #[error("testfixtures: {path} is not a valid UTF-8 file name")]
+    InvalidFileName { path: String },
+
+    /// A fixture file is not valid YAML. The message includes the line and column.
+    #[error("testfixtures: failed to parse {path}: {source}")]
+    FixtureParse { path: String, source: ScanError },
+
+    /// A string is not in any of the supported datetime formats.
+    #[error("testfixtures: '{value}' is invalid format")]
+    InvalidDate { value: String },
+
+    /// A table of a fixture file does not exist in the database.
+    #[error("testfixtures: {table} of {path} does not exist")]
+    UnknownTable { path: String, table: String },
+
+    /// A record does not fit the columns of its table. `reason` says how:
+    /// the column does not exist, a NOT NULL column without a default is left out,
+    /// or the value can not be stored in the column.
+    #[error("testfixtures: record {record_index} of {table} in {path}: column {column} {reason}")]
+    InvalidRecord {
+        path: String,
+        table: String,
+        record_index: usize,
+        column: String,
+        reason: String,
+    },
+
+    /// A table or column name can not be quoted safely.
+    #[error("testfixtures: {identifier:?} can not be used as a table or column name")]
+    InvalidIdentifier { identifier: String },
+
+    /// A table cleaned with [Cleanup::DeleteWhereFixtureKeys](crate::Cleanup::DeleteWhereFixtureKeys) has no primary key.
+    #[error("testfixtures: {table} has no primary key to delete fixture rows by")]
+    MissingPrimaryKey { table: String },
+
+    /// A `$ref(...)` points to a record or column which does not exist.
+    #[error("testfixtures: unresolved reference '{reference}' in record '{record}' of {path}")]
+    UnresolvedReference {
+        path: String,
+        record: String,
+        reference: String,
+    },
+
+    /// A `$ref(...)` eventually points to itself.
+    #[error("testfixtures: circular reference '{reference}' in record '{record}' of {path}")]
+    CircularReference {
+        path: String,
+        record: String,
+        reference: String,
+    },
+
+    /// A query for a table failed.
+    /// `record_index` is the index of the first record inserted by the query, or `None` when the table was being cleaned.
+    #[error(
+        "testfixtures: {}: {source}",
+        match record_index {
+            Some(index) => format!("failed to insert record {} into {}", index, table),
+            None => format!("failed to clean {}", table),
+        }
+    )]
+    Query {
+        table: String,
+        record_index: Option<usize>,
+        source: sqlx::Error,
+    },
+
+    /// Several errors were found in fixture files, like files which can not be parsed
+    /// or records which do not fit the schema.
+    #[error(
+        "testfixtures: {} fixture errors:\n{}",
+        .0.len(),
+        .0.iter().map(|err| format!("  {}", err)).collect::<Vec<_>>().join("\n")
+    )]
+    Fixtures(Vec<Error>),
+
+    /// Any other database error.
+    #[error("testfixtures: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+
+    #[test]
+    fn test_query_error() {
+        let err = Error::Query {
+            table: "todos".to_string(),
+            record_index: Some(1),
+            source: sqlx::Error::RowNotFound,
+        };
+        assert_eq!(
+            err.to_string(),
+            "testfixtures: failed to insert record 1 into todos: found no row when we expected at least one"
+        );
+
+        let err = Error::Query {
+            table: "todos".to_string(),
+            record_index: None,
+            source: sqlx::Error::RowNotFound,
+        };
+        assert_eq!(
+            err.to_string(),
+            "testfixtures: failed to clean todos: found no row when we expected at least one"
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use chrono::{DateTime, TimeZone};
+use crate::error::Error;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone};
+use std::fmt::Display;
 use std::fs::File;
 use std::path::Path;
 
@@ -7,13 +9,24 @@ pub struct FixtureFile<Tz: TimeZone + Send + Sync> {
     pub path: String,
     pub file_name: String,
     pub content: File,
+    /// Tables the file has records for.
+    /// This is the file stem unless the file maps table names to lists of records.
+    pub tables: Vec<String>,
     pub insert_sqls: Vec<InsertSql<Tz>>,
 }
 
 /// SQL query and parameters.
 pub struct InsertSql<Tz: TimeZone + Send + Sync> {
+    pub table: String,
+    /// Index of the record in its table.
+    pub record_index: usize,
     pub sql: String,
     pub params: Vec<SqlParam<Tz>>,
+    /// Columns of the inserted records.
+    pub columns: Vec<String>,
+    /// Values of each record in the order of `columns`, as indexes into `params`.
+    /// A `RAW=` value has no parameter and is `None`.
+    pub rows: Vec<Vec<Option<usize>>>,
 }
 
 /// SQL parameter types.
@@ -23,9 +36,50 @@ where
 {
     String(String),
     Datetime(DateTime<Tz>),
-    Integer(u32),
-    Float(f32),
+    Integer(i64),
+    /// An integer which does not fit in `i64`.
+    UnsignedInteger(u64),
+    Float(f64),
+    /// The exact textual form of a decimal number.
+    Decimal(String),
     Boolean(bool),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Binary(Vec<u8>),
+    /// JSON text for a JSON column.
+    Json(String),
+    /// A value of an enum column.
+    Enum(String),
+    Null,
+}
+
+impl<Tz> SqlParam<Tz>
+where
+    Tz: TimeZone + Send + Sync,
+    Tz::Offset: Display,
+{
+    /// Bytes which identify the parameter for a checksum, the same on every platform and Rust release.
+    /// Datetimes have their offsets, so they change with the location they are read in.
+    pub(crate) fn checksum_bytes(&self) -> Vec<u8> {
+        let (tag, bytes) = match self {
+            SqlParam::String(param) => (0, param.as_bytes().to_vec()),
+            SqlParam::Datetime(param) => (1, param.to_rfc3339().into_bytes()),
+            SqlParam::Integer(param) => (2, param.to_le_bytes().to_vec()),
+            SqlParam::UnsignedInteger(param) => (3, param.to_le_bytes().to_vec()),
+            SqlParam::Float(param) => (4, param.to_bits().to_le_bytes().to_vec()),
+            SqlParam::Decimal(param) => (5, param.as_bytes().to_vec()),
+            SqlParam::Boolean(param) => (6, vec![*param as u8]),
+            SqlParam::Date(param) => (7, param.to_string().into_bytes()),
+            SqlParam::Time(param) => (8, param.to_string().into_bytes()),
+            SqlParam::Binary(param) => (9, param.clone()),
+            SqlParam::Json(param) => (10, param.as_bytes().to_vec()),
+            SqlParam::Enum(param) => (11, param.as_bytes().to_vec()),
+            SqlParam::Null => (12, vec![]),
+        };
+        let mut checksum_bytes = vec![tag];
+        checksum_bytes.extend(bytes);
+        checksum_bytes
+    }
 }
 
 impl<Tz> FixtureFile<Tz>
@@ -41,8 +95,16 @@ where
             .to_string()
     }
 
-    pub(crate) fn delete(&self) -> String {
-        format!("DELETE FROM {}", self.file_stem())
+    /// DELETE queries for the tables of the file, paired with the table names.
+    /// Table names are quoted with `quote`.
+    pub(crate) fn delete<F>(&self, quote: F) -> Result<Vec<(String, String)>, Error>
+    where
+        F: Fn(&str) -> Result<String, Error>,
+    {
+        self.tables
+            .iter()
+            .map(|table| Ok((table.clone(), format!("DELETE FROM {}", quote(table)?))))
+            .collect()
     }
 }
 
@@ -80,6 +142,7 @@ mod tests {
                 .unwrap()
                 .to_string(),
             content: File::open(fixture_file_path.clone()).unwrap(),
+            tables: vec![],
             insert_sqls: vec![],
         };
 
@@ -113,10 +176,17 @@ mod tests {
                 .unwrap()
                 .to_string(),
             content: File::open(fixture_file_path.clone()).unwrap(),
+            tables: vec!["todos".to_string(), "users".to_string()],
             insert_sqls: vec![],
         };
 
-        assert_eq!(fixture_file.delete(), "DELETE FROM todos");
+        assert_eq!(
+            fixture_file.delete(|x| Ok(format!("`{}`", x)))?,
+            vec![
+                ("todos".to_string(), "DELETE FROM `todos`".to_string()),
+                ("users".to_string(), "DELETE FROM `users`".to_string())
+            ]
+        );
         Ok(())
     }
 }
@@ -1,30 +1,472 @@
-use crate::fixture_file::FixtureFile;
+use crate::cleanup::Cleanup;
+use crate::column_type::Column;
+use crate::error::Error;
+use crate::fixture_file::{FixtureFile, SqlParam};
 use async_trait::async_trait;
 use chrono::{Offset, TimeZone};
 use sqlx::{Connect, Connection, Database as DB, Pool};
+use std::collections::HashMap;
 
 /// Represents a type that execute SQL queries.
+/// Only `init`, `database_name` and `with_transaction` have to be implemented.
+/// The options which need any other method fail with [Error::Unsupported] unless it is implemented.
 #[async_trait]
-pub trait Database<D, C, O, Tz>
+pub trait Database<D, C, O, Tz>: Send + Sync
 where
     D: DB + Sync + Send,
-    C: Connection<Database = D> + Connect<Database = D> + Sync + Send,
+    C: Connection<Database = D> + Connect<Database = D> + Send,
     O: Offset + Sync + Send,
     Tz: TimeZone<Offset = O> + Send + Sync,
 {
     /// Initialize Database struct.
-    async fn init(&mut self, db: &Pool<C>) -> anyhow::Result<()>;
+    async fn init(&mut self, db: &Pool<C>) -> Result<(), Error>;
 
     /// Get database name by excuting SQL query.
-    async fn database_name(&self, db: &Pool<C>) -> anyhow::Result<String>;
+    async fn database_name(&self, db: &Pool<C>) -> Result<String, Error>;
 
-    // TODO: complete this function
-    // async fn table_names(&self, db: &Pool<C>) -> anyhow::Result<Vec<String>>;
+    /// Get the address or host name of the database server, or `None` if it can not be determined.
+    /// [require_local_host](crate::loader::Loader::require_local_host) is not supported without it.
+    async fn server_address(&self, _db: &Pool<C>) -> Result<Option<String>, Error> {
+        Err(unsupported("require_local_host"))
+    }
+
+    /// Get names of all base tables in the database by excuting SQL query.
+    /// [clean_all_tables](crate::loader::Loader::clean_all_tables) is not supported without it.
+    async fn table_names(&self, _db: &Pool<C>) -> Result<Vec<String>, Error> {
+        Err(unsupported("clean_all_tables"))
+    }
+
+    /// Get a fingerprint of the rows in `tables`, which changes when any of the rows change.
+    /// [skip_unchanged](crate::loader::Loader::skip_unchanged) is not supported without it.
+    async fn fingerprint(&self, _db: &Pool<C>, _tables: &[String]) -> Result<String, Error> {
+        Err(unsupported("skip_unchanged"))
+    }
+
+    /// Get the checksum saved by the last load, creating the bookkeeping table if it does not exist.
+    /// [skip_unchanged](crate::loader::Loader::skip_unchanged) is not supported without it.
+    async fn load_checksum(&self, _db: &Pool<C>) -> Result<Option<String>, Error> {
+        Err(unsupported("skip_unchanged"))
+    }
+
+    /// Save the checksum of a load in the bookkeeping table.
+    /// [skip_unchanged](crate::loader::Loader::skip_unchanged) is not supported without it.
+    async fn save_checksum(&self, _db: &Pool<C>, _checksum: &str) -> Result<(), Error> {
+        Err(unsupported("skip_unchanged"))
+    }
+
+    /// Get the columns of each table read by [init](Database::init), keyed like table names in fixture files.
+    /// Fixtures are checked against them and values are converted to their types.
+    /// It is `None` if the schema has not been read, and values are bound as their YAML types say.
+    fn schema(&self) -> Option<&HashMap<String, Vec<Column>>> {
+        None
+    }
+
+    /// Quote a table or column name. It is quoted with double quotes as in standard SQL by default.
+    fn quote_identifier(&self, identifier: &str) -> Result<String, Error> {
+        quote_identifier(identifier, '"')
+    }
+
+    /// Render the placeholder of the `index`th bind parameter in a query, starting from 1.
+    /// It is `?` unless the database numbers placeholders.
+    fn placeholder(&self, _index: usize) -> String {
+        "?".to_string()
+    }
+
+    /// The maximum number of bind parameters in a query.
+    /// Records are inserted in batches which do not exceed it.
+    /// It is 999 by default, which is the lowest limit of the supported databases.
+    fn max_bind_params(&self) -> usize {
+        999
+    }
 
     /// Execute SQL queries in a transaction.
+    /// Tables are cleaned according to `cleanup` first.
+    /// If `clean_all_tables` is true, tables which have no fixture file are also cleaned.
     async fn with_transaction(
         &self,
         pool: &Pool<C>,
         fixture_files: &[FixtureFile<Tz>],
-    ) -> anyhow::Result<()>;
+        cleanup: Cleanup,
+        clean_all_tables: bool,
+    ) -> Result<(), Error>;
+
+    /// Execute the SQL queries of [with_transaction](Database::with_transaction) on `conn` without committing them,
+    /// so they can run in a transaction owned by the caller.
+    /// [load_into](crate::loader::Loader::load_into) is not supported without it.
+    async fn execute(
+        &self,
+        _conn: &mut C,
+        _fixture_files: &[FixtureFile<Tz>],
+        _cleanup: Cleanup,
+        _clean_all_tables: bool,
+    ) -> Result<(), Error> {
+        Err(unsupported("load_into"))
+    }
+}
+
+/// The error of an option which a [Database] does not implement.
+fn unsupported(option: &str) -> Error {
+    Error::Unsupported {
+        option: option.to_string(),
+        database: "this database".to_string(),
+    }
+}
+
+/// The bookkeeping table which holds the checksum of the last load.
+pub(crate) const CHECKSUM_TABLE: &str = "testfixtures_checksum";
+
+/// A query to clean a table, with the table name and bind parameters.
+pub(crate) type CleanupQuery<'a, Tz> = (String, String, Vec<&'a SqlParam<Tz>>);
+
+/// Build queries to clean tables that have a fixture file according to `cleanup`.
+/// If `clean_all_tables` is true, the other tables in `table_names` are deleted,
+/// or truncated with [Cleanup::Truncate].
+/// Table names are quoted with `quote`. `truncate` builds TRUNCATE queries
+/// from pairs of table names and quoted table names.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cleanup_queries<'a, D, C, O, Tz, Q, F>(
+    helper: &dyn Database<D, C, O, Tz>,
+    fixture_files: &'a [FixtureFile<Tz>],
+    table_names: &[String],
+    primary_keys: &HashMap<String, Vec<String>>,
+    cleanup: Cleanup,
+    clean_all_tables: bool,
+    quote: Q,
+    truncate: F,
+) -> Result<Vec<CleanupQuery<'a, Tz>>, Error>
+where
+    D: DB + Sync + Send,
+    C: Connection<Database = D> + Connect<Database = D> + Send,
+    O: Offset + Sync + Send,
+    Tz: TimeZone<Offset = O> + Send + Sync,
+    Q: Fn(&str) -> Result<String, Error>,
+    F: Fn(&[(String, String)]) -> Vec<(String, String)>,
+{
+    let fixture_tables: Vec<String> = fixture_files
+        .iter()
+        .flat_map(|x| x.tables.clone())
+        .collect();
+    let other_tables: Vec<String> = if clean_all_tables {
+        table_names
+            .iter()
+            .filter(|x| !fixture_tables.contains(x))
+            .cloned()
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let mut queries = vec![];
+    match cleanup {
+        Cleanup::Delete => {
+            for fixture_file in fixture_files {
+                for (table, sql) in fixture_file.delete(&quote)? {
+                    queries.push((table, sql, vec![]));
+                }
+            }
+        }
+        Cleanup::Truncate => {
+            let tables = fixture_tables
+                .iter()
+                .chain(other_tables.iter())
+                .map(|x| Ok((x.clone(), quote(x)?)))
+                .collect::<Result<Vec<(String, String)>, Error>>()?;
+            if tables.is_empty() {
+                return Ok(queries);
+            }
+            for (table, sql) in truncate(&tables) {
+                queries.push((table, sql, vec![]));
+            }
+            return Ok(queries);
+        }
+        Cleanup::DeleteWhereFixtureKeys => {
+            for table in &fixture_tables {
+                let primary_key = match primary_keys.get(table) {
+                    Some(primary_key) if !primary_key.is_empty() => primary_key,
+                    _ => {
+                        return Err(Error::MissingPrimaryKey {
+                            table: table.clone(),
+                        })
+                    }
+                };
+                queries.append(&mut delete_by_keys(
+                    helper,
+                    fixture_files,
+                    table,
+                    &quote(table)?,
+                    primary_key,
+                )?);
+            }
+        }
+        Cleanup::None => (),
+    }
+    for table in &other_tables {
+        queries.push((
+            table.clone(),
+            format!("DELETE FROM {}", quote(table)?),
+            vec![],
+        ));
+    }
+    Ok(queries)
+}
+
+/// Build DELETE queries for rows of `table` whose primary keys appear in the fixtures.
+/// Keys are split into queries which do not exceed the maximum number of bind parameters.
+fn delete_by_keys<'a, D, C, O, Tz>(
+    helper: &dyn Database<D, C, O, Tz>,
+    fixture_files: &'a [FixtureFile<Tz>],
+    table: &str,
+    quoted_table: &str,
+    primary_key: &[String],
+) -> Result<Vec<CleanupQuery<'a, Tz>>, Error>
+where
+    D: DB + Sync + Send,
+    C: Connection<Database = D> + Connect<Database = D> + Send,
+    O: Offset + Sync + Send,
+    Tz: TimeZone<Offset = O> + Send + Sync,
+{
+    let mut keys = vec![];
+    for insert_sql in fixture_files
+        .iter()
+        .flat_map(|x| x.insert_sqls.iter())
+        .filter(|x| x.table == table)
+    {
+        let positions: Option<Vec<usize>> = primary_key
+            .iter()
+            .map(|column| insert_sql.columns.iter().position(|x| x == column))
+            .collect();
+        let positions = match positions {
+            Some(positions) => positions,
+            None => continue,
+        };
+        for row in &insert_sql.rows {
+            let key: Option<Vec<&SqlParam<Tz>>> = positions
+                .iter()
+                .map(|position| row[*position].map(|index| &insert_sql.params[index]))
+                .collect();
+            if let Some(key) = key {
+                keys.push(key);
+            }
+        }
+    }
+
+    let columns = primary_key
+        .iter()
+        .map(|x| helper.quote_identifier(x))
+        .collect::<Result<Vec<String>, Error>>()?
+        .join(", ");
+    let keys_per_query = (helper.max_bind_params() / primary_key.len()).max(1);
+    let mut queries = vec![];
+    for chunk in keys.chunks(keys_per_query) {
+        let mut index = 0;
+        let tuples: Vec<String> = chunk
+            .iter()
+            .map(|key| {
+                let placeholders: Vec<String> = key
+                    .iter()
+                    .map(|_| {
+                        index += 1;
+                        helper.placeholder(index)
+                    })
+                    .collect();
+                match placeholders.len() {
+                    1 => placeholders.join(""),
+                    _ => format!("({})", placeholders.join(", ")),
+                }
+            })
+            .collect();
+        let sql = match primary_key.len() {
+            1 => format!(
+                "DELETE FROM {} WHERE {} IN ({})",
+                quoted_table,
+                columns,
+                tuples.join(", ")
+            ),
+            _ => format!(
+                "DELETE FROM {} WHERE ({}) IN ({})",
+                quoted_table,
+                columns,
+                tuples.join(", ")
+            ),
+        };
+        queries.push((table.to_string(), sql, chunk.concat()));
+    }
+    Ok(queries)
+}
+
+/// Group columns, given as schema and table names with a column, by table.
+/// Each table is keyed by its name qualified with the schema,
+/// and also by its bare name if it is in `current_schema`.
+pub(crate) fn columns_by_table<T: Clone>(
+    columns: Vec<(String, String, T)>,
+    current_schema: &str,
+) -> HashMap<String, Vec<T>> {
+    let mut tables: HashMap<String, Vec<T>> = HashMap::new();
+    for (schema, table, column) in columns {
+        if schema == current_schema {
+            tables
+                .entry(table.clone())
+                .or_default()
+                .push(column.clone());
+        }
+        tables
+            .entry(format!("{}.{}", schema, table))
+            .or_default()
+            .push(column);
+    }
+    tables
+}
+
+/// Quote a table name which can be qualified by a schema, like `billing.invoices`.
+pub(crate) fn quote_table(table: &str, quote: char) -> Result<String, Error> {
+    quote_qualified(table, |x| quote_identifier(x, quote))
+}
+
+/// Quote each part of a table name which can be qualified by a schema with `quote_identifier`.
+pub(crate) fn quote_qualified<F>(table: &str, quote_identifier: F) -> Result<String, Error>
+where
+    F: Fn(&str) -> Result<String, Error>,
+{
+    let parts = table
+        .split('.')
+        .map(quote_identifier)
+        .collect::<Result<Vec<String>, Error>>()?;
+    Ok(parts.join("."))
+}
+
+/// Quote an identifier with `quote`, doubling the quote characters in it.
+/// Empty identifiers and identifiers which contain a NUL character can not be quoted.
+pub(crate) fn quote_identifier(identifier: &str, quote: char) -> Result<String, Error> {
+    if identifier.is_empty() || identifier.contains('\0') {
+        return Err(Error::InvalidIdentifier {
+            identifier: identifier.to_string(),
+        });
+    }
+    let escaped = identifier.replace(quote, &format!("{}{}", quote, quote));
+    Ok(format!("{}{}{}", quote, escaped, quote))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cleanup::Cleanup;
+    use crate::error::Error;
+    use crate::fixture_file::FixtureFile;
+    use crate::helper::{columns_by_table, quote_identifier, quote_table, Database as DB};
+    use async_trait::async_trait;
+    use chrono::{Offset, TimeZone, Utc};
+    use sqlx::{MySql as M, MySqlConnection, MySqlPool};
+
+    /// A helper which implements only the required methods.
+    struct MinimalDatabase;
+
+    #[async_trait]
+    impl<O, Tz> DB<M, MySqlConnection, O, Tz> for MinimalDatabase
+    where
+        O: Offset + Sync + Send + 'static,
+        Tz: TimeZone<Offset = O> + Send + Sync + 'static,
+    {
+        async fn init(&mut self, _pool: &MySqlPool) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn database_name(&self, _pool: &MySqlPool) -> Result<String, Error> {
+            Ok("test".to_string())
+        }
+
+        async fn with_transaction(
+            &self,
+            _pool: &MySqlPool,
+            _fixture_files: &[FixtureFile<Tz>],
+            _cleanup: Cleanup,
+            _clean_all_tables: bool,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_default_methods() -> anyhow::Result<()> {
+        let helper: &dyn DB<M, MySqlConnection, Utc, Utc> = &MinimalDatabase;
+        assert_eq!(helper.quote_identifier("order")?, r#""order""#);
+        assert_eq!(helper.placeholder(2), "?");
+        assert_eq!(helper.max_bind_params(), 999);
+        assert!(helper.schema().is_none());
+
+        // The pool never connects, since the default methods do not use it.
+        let pool = MySqlPool::new("mysql://root@127.0.0.1:1/test").await?;
+        match helper.table_names(&pool).await {
+            Err(err @ Error::Unsupported { .. }) => assert_eq!(
+                err.to_string(),
+                "testfixtures: clean_all_tables is not supported on this database"
+            ),
+            _ => panic!("expected an unsupported error"),
+        }
+        assert!(matches!(
+            helper.server_address(&pool).await,
+            Err(Error::Unsupported { .. })
+        ));
+        assert!(matches!(
+            helper.fingerprint(&pool, &[]).await,
+            Err(Error::Unsupported { .. })
+        ));
+        assert!(matches!(
+            helper.load_checksum(&pool).await,
+            Err(Error::Unsupported { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_columns_by_table() {
+        let tables = columns_by_table(
+            vec![
+                ("public".to_string(), "todos".to_string(), "id".to_string()),
+                (
+                    "billing".to_string(),
+                    "invoices".to_string(),
+                    "amount".to_string(),
+                ),
+            ],
+            "public",
+        );
+        assert_eq!(tables["todos"], vec!["id".to_string()]);
+        assert_eq!(tables["public.todos"], vec!["id".to_string()]);
+        assert_eq!(tables["billing.invoices"], vec!["amount".to_string()]);
+        assert!(!tables.contains_key("invoices"));
+    }
+
+    #[test]
+    fn test_quote_identifier() -> anyhow::Result<()> {
+        assert_eq!(quote_identifier("order", '`')?, "`order`");
+        assert_eq!(quote_identifier("key", '"')?, r#""key""#);
+        assert_eq!(quote_identifier("a`b", '`')?, "`a``b`");
+        assert_eq!(
+            quote_identifier(r#"id"); DROP TABLE todos; --"#, '"')?,
+            r#""id""); DROP TABLE todos; --""#
+        );
+        assert!(matches!(
+            quote_identifier("", '"'),
+            Err(Error::InvalidIdentifier { .. })
+        ));
+        assert!(matches!(
+            quote_identifier("a\0b", '"'),
+            Err(Error::InvalidIdentifier { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_table() -> anyhow::Result<()> {
+        assert_eq!(quote_table("todos", '"')?, r#""todos""#);
+        assert_eq!(
+            quote_table("billing.invoices", '"')?,
+            r#""billing"."invoices""#
+        );
+        assert!(matches!(
+            quote_table("billing.", '`'),
+            Err(Error::InvalidIdentifier { .. })
+        ));
+        Ok(())
+    }
 }
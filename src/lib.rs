@@ -46,13 +46,40 @@
 //! }
 //! ```
 
+mod cleanup;
+mod column_type;
+mod dumper;
+mod error;
 mod fixture_file;
 mod helper;
 mod loader;
 mod mysql;
+mod postgresql;
+mod sqlite;
+mod test_database;
 
+pub use cleanup::Cleanup;
+pub use column_type::ColumnType;
+pub use dumper::{Dump, Dumper};
+pub use error::Error;
 pub use fixture_file::{FixtureFile, InsertSql, SqlParam};
 pub use helper::Database;
 pub use loader::Loader;
 pub use mysql::helper::MySql;
 pub use mysql::loader::MySqlLoader;
+pub use mysql::test_database::MySqlTestDatabase;
+pub use postgresql::helper::PostgreSql;
+pub use postgresql::loader::PostgresLoader;
+pub use postgresql::test_database::PostgresTestDatabase;
+pub use sqlite::helper::Sqlite;
+pub use sqlite::loader::SqliteLoader;
+pub use test_database::{Provision, TestDatabase};
+pub use testfixtures_macros::test;
+
+/// Crates used by code generated by [test](macro@test).
+#[doc(hidden)]
+pub mod export {
+    pub use crate::test_database::block_on;
+    pub use chrono;
+    pub use sqlx;
+}
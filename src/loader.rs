@@ -1,12 +1,21 @@
+use crate::cleanup::Cleanup;
+use crate::column_type::{yaml_text, yaml_to_json, Column, ColumnType};
+use crate::error::Error;
 use crate::fixture_file::{FixtureFile, InsertSql, SqlParam};
-use crate::helper::Database as DB;
-use chrono::{DateTime, Offset, TimeZone};
+use crate::helper::{quote_qualified, Database as DB};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
 use regex::Regex;
+use sha2::{Digest, Sha256};
+use sqlx::types::BigDecimal;
 use sqlx::{Connect, Connection, Database, Pool};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::env;
 use std::fmt::Display;
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::net::IpAddr;
 use std::path::Path;
 use std::str::FromStr;
 use yaml_rust::{Yaml, YamlLoader};
@@ -15,7 +24,7 @@ use yaml_rust::{Yaml, YamlLoader};
 pub struct Loader<D, C, O, Tz>
 where
     D: Database + Sync + Send,
-    C: Connection<Database = D> + Connect<Database = D> + Sync + Send,
+    C: Connection<Database = D> + Connect<Database = D> + Send,
     O: Offset,
     Tz: TimeZone<Offset = O> + Send + Sync,
 {
@@ -23,13 +32,27 @@ where
     pub helper: Option<Box<dyn DB<D, C, O, Tz>>>,
     pub fixture_files: Vec<FixtureFile<Tz>>,
     pub skip_test_database_check: bool,
+    /// A pattern the database name has to match.
+    pub test_database_pattern: Option<Regex>,
+    /// Names the database name has to be one of.
+    pub allowed_databases: Vec<String>,
+    /// Whether the database server has to be on localhost or a private network.
+    pub require_local_host: bool,
+    /// Environment variables which have to be set.
+    pub required_env_vars: Vec<String>,
+    pub clean_all_tables: bool,
+    pub cleanup: Cleanup,
+    pub skip_unchanged: bool,
     pub location: Option<Tz>,
+    pub schema: Option<String>,
+    /// Errors found while reading fixture files, returned when the loader is created.
+    pub(crate) errors: Vec<Error>,
 }
 
 impl<D, C, O, Tz> Default for Loader<D, C, O, Tz>
 where
     D: Database + Sync + Send,
-    C: Connection<Database = D> + Connect<Database = D> + Sync + Send,
+    C: Connection<Database = D> + Connect<Database = D> + Send,
     O: Offset,
     Tz: TimeZone<Offset = O> + Send + Sync,
 {
@@ -39,7 +62,16 @@ where
             helper: None,
             fixture_files: vec![],
             skip_test_database_check: false,
+            test_database_pattern: None,
+            allowed_databases: vec![],
+            require_local_host: false,
+            required_env_vars: vec![],
+            clean_all_tables: false,
+            cleanup: Cleanup::default(),
+            skip_unchanged: false,
             location: None,
+            schema: None,
+            errors: vec![],
         }
     }
 }
@@ -47,24 +79,133 @@ where
 impl<D, C, O, Tz> Loader<D, C, O, Tz>
 where
     D: Database + Sync + Send,
-    C: Connection<Database = D> + Connect<Database = D> + Sync + Send,
+    C: Connection<Database = D> + Connect<Database = D> + Send,
     O: Offset + Display + Send + Sync,
     Tz: TimeZone<Offset = O> + Send + Sync,
 {
     /// Execute SQL queries builded from yaml files.
-    pub async fn load(&self) -> anyhow::Result<()> {
+    pub async fn load(&self) -> Result<(), Error> {
         if !self.skip_test_database_check {
-            if let Err(err) = self.ensure_test_database().await {
-                return Err(anyhow::anyhow!("testfixtures: {}", err));
-            }
+            self.ensure_test_database().await?;
+        }
+
+        let helper = self.helper.as_ref().unwrap();
+        let pool = self.pool.as_ref().unwrap();
+        if self.skip_unchanged && helper.load_checksum(pool).await? == Some(self.checksum().await?)
+        {
+            return Ok(());
+        }
+
+        helper
+            .with_transaction(
+                pool,
+                &self.fixture_files,
+                self.cleanup,
+                self.clean_all_tables,
+            )
+            .await?;
+        if self.skip_unchanged {
+            helper.save_checksum(pool, &self.checksum().await?).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute SQL queries builded from yaml files on `conn`, which is usually a transaction owned by the test.
+    /// Nothing is committed, so rolling the transaction back at the end of the test undoes the fixtures.
+    /// [skip_unchanged](Loader::skip_unchanged) is ignored.
+    ///
+    /// On MySQL, auto increment counters are not reset and [Cleanup::Truncate](crate::Cleanup::Truncate) should not be used,
+    /// since `ALTER TABLE` and `TRUNCATE TABLE` commit the transaction implicitly.
+    ///
+    /// # Example
+    /// ```rust
+    /// #[cfg(test)]
+    /// mod tests {
+    ///     use chrono::Utc;
+    ///     use sqlx::PgPool;
+    ///     use std::env;
+    ///     use testfixtures::PostgresLoader;
+    ///     #[async_std::test]
+    ///     async fn test_something() -> anyhow::Result<()> {
+    ///         let pool = PgPool::new(&env::var("DATABASE_URL")?).await?;
+    ///         let loader = PostgresLoader::new(|cfg| {
+    ///             cfg.location(Utc);
+    ///             cfg.database(pool.clone());
+    ///             cfg.paths(vec!["fixtures/todos.yml"]);
+    ///         })
+    ///         .await?;
+    ///         let mut tx = pool.begin().await?;
+    ///         loader.load_into(&mut tx).await?;
+    ///
+    ///         // run your tests with `&mut tx`
+    ///
+    ///         tx.rollback().await?;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    pub async fn load_into(&self, conn: &mut C) -> Result<(), Error> {
+        if !self.skip_test_database_check {
+            self.ensure_test_database().await?;
         }
 
         self.helper
             .as_ref()
             .unwrap()
-            .with_transaction(self.pool.as_ref().unwrap(), &self.fixture_files)
-            .await?;
-        Ok(())
+            .execute(
+                conn,
+                &self.fixture_files,
+                self.cleanup,
+                self.clean_all_tables,
+            )
+            .await
+    }
+
+    /// Checksum of the fixtures and the options to load them,
+    /// combined with the fingerprint of the tables they are loaded into.
+    /// It is saved in the database, so it is a SHA-256 of bytes which do not depend on the platform or the Rust release.
+    /// The location is included through the offsets of datetimes, which are the only values it changes.
+    async fn checksum(&self) -> Result<String, Error> {
+        let helper = self.helper.as_ref().unwrap();
+        let pool = self.pool.as_ref().unwrap();
+        let mut tables: Vec<String> = self
+            .fixture_files
+            .iter()
+            .flat_map(|x| x.tables.clone())
+            .collect();
+        if self.clean_all_tables {
+            for table in helper.table_names(pool).await? {
+                if !tables.contains(&table) {
+                    tables.push(table);
+                }
+            }
+        }
+
+        let fingerprint = helper.fingerprint(pool, &tables).await?;
+
+        let mut hasher = Sha256::new();
+        // Each value is prefixed with its length, so values can not run into each other.
+        let mut write = |bytes: &[u8]| {
+            hasher.input((bytes.len() as u64).to_le_bytes());
+            hasher.input(bytes);
+        };
+        write(format!("{:?}", self.cleanup).as_bytes());
+        write(&[self.clean_all_tables as u8]);
+        write(self.schema.as_deref().unwrap_or_default().as_bytes());
+        for fixture_file in &self.fixture_files {
+            for insert_sql in &fixture_file.insert_sqls {
+                write(insert_sql.sql.as_bytes());
+                for param in &insert_sql.params {
+                    write(&param.checksum_bytes());
+                }
+            }
+        }
+        write(fingerprint.as_bytes());
+        Ok(hasher
+            .result()
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect())
     }
 
     /// Set database pool.
@@ -77,31 +218,83 @@ where
         self.skip_test_database_check = true
     }
 
+    /// Check that the database name matches `pattern` instead of ending with `test`.
+    pub fn test_database_pattern(&mut self, pattern: &str) {
+        match Regex::new(pattern) {
+            Ok(pattern) => self.test_database_pattern = Some(pattern),
+            Err(err) => self.errors.push(Error::InvalidPattern {
+                pattern: pattern.to_string(),
+                source: err,
+            }),
+        }
+    }
+
+    /// Check that the database name is one of `names` instead of ending with `test`.
+    pub fn allow_databases(&mut self, names: Vec<&str>) {
+        self.allowed_databases
+            .extend(names.into_iter().map(|x| x.to_string()))
+    }
+
+    /// Check that the database server is on localhost or a private address.
+    /// MySQL does not report its address, so this option fails with [Error::Unsupported] there.
+    pub fn require_local_host(&mut self) {
+        self.require_local_host = true
+    }
+
+    /// Check that the environment variable `name` is set.
+    pub fn require_env(&mut self, name: &str) {
+        self.required_env_vars.push(name.to_string())
+    }
+
+    /// Clean all tables in the database before loading, not only tables that have a fixture file.
+    pub fn clean_all_tables(&mut self) {
+        self.clean_all_tables = true
+    }
+
+    /// Set how tables are cleaned before loading. [Cleanup::Delete] is the default.
+    pub fn cleanup(&mut self, cleanup: Cleanup) {
+        self.cleanup = cleanup
+    }
+
+    /// Skip loading when neither the fixtures nor the tables have changed since the last load.
+    /// A checksum of the fixtures and a fingerprint of the tables are saved in a bookkeeping table.
+    pub fn skip_unchanged(&mut self) {
+        self.skip_unchanged = true
+    }
+
     /// Set timezone.
     pub fn location(&mut self, location: Tz) {
         self.location = Some(location)
     }
 
+    /// Set a schema (a database for MySQL) for tables which are not qualified by the file name.
+    pub fn schema(&mut self, schema: &str) {
+        self.schema = Some(schema.to_string())
+    }
+
     /// Set fixture files directly.
     pub fn files(&mut self, files: Vec<&str>) {
-        let mut fixtures = Self::fixtures_from_files(files);
-        self.fixture_files.append(&mut fixtures)
+        let (mut fixtures, mut errors) = Self::fixtures_from_files(files);
+        self.fixture_files.append(&mut fixtures);
+        self.errors.append(&mut errors)
     }
 
     /// Set fixture files from a directory.
     pub fn directory(&mut self, directory: &str) {
-        let mut fixtures = Self::fixtures_from_directory(directory);
-        self.fixture_files.append(&mut fixtures)
+        let (mut fixtures, mut errors) = Self::fixtures_from_directory(directory);
+        self.fixture_files.append(&mut fixtures);
+        self.errors.append(&mut errors)
     }
 
     /// This option is a combination of files option and directory option.
     pub fn paths(&mut self, paths: Vec<&str>) {
-        let mut fixtures = Self::fixtures_from_paths(paths);
-        self.fixture_files.append(&mut fixtures)
+        let (mut fixtures, mut errors) = Self::fixtures_from_paths(paths);
+        self.fixture_files.append(&mut fixtures);
+        self.errors.append(&mut errors)
     }
 
     /// Try change str to datetime.
-    fn try_str_to_date(&self, s: String) -> anyhow::Result<DateTime<Tz>> {
+    fn try_str_to_date(&self, s: String) -> Result<DateTime<Tz>, Error> {
         let formats = vec![
             "%Y-%m-%d %H:%M",
             "%Y-%m-%d %H:%M:%S",
@@ -113,191 +306,911 @@ where
             "%Y/%m/%d %H:%M:%S",
         ];
         for f in formats {
-            let result = self
-                .location
-                .as_ref()
-                .unwrap()
-                .datetime_from_str(s.as_str(), f);
+            let result = NaiveDateTime::parse_from_str(s.as_str(), f);
             if let Ok(datetime) = result {
-                return Ok(datetime);
+                if let Some(datetime) = self
+                    .location
+                    .as_ref()
+                    .unwrap()
+                    .from_local_datetime(&datetime)
+                    .single()
+                {
+                    return Ok(datetime);
+                }
             }
         }
-        Err(anyhow::anyhow!("testfixtures: '{}' is invalid format", s))
+        Err(Error::InvalidDate { value: s })
     }
 
     /// Set fixture file content to FixtureFile struct.
-    fn fixtures_from_files(files: Vec<&str>) -> Vec<FixtureFile<Tz>> {
+    fn fixtures_from_files(files: Vec<&str>) -> (Vec<FixtureFile<Tz>>, Vec<Error>) {
         let mut fixture_files: Vec<FixtureFile<Tz>> = vec![];
+        let mut errors = vec![];
         for f in files {
-            let fixture = FixtureFile {
-                path: f.to_string(),
-                file_name: Path::new(f)
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string(),
-                content: File::open(f).unwrap(),
-                insert_sqls: vec![],
+            let file_name = match Path::new(f).file_name().and_then(|x| x.to_str()) {
+                Some(file_name) => file_name.to_string(),
+                None => {
+                    errors.push(Error::InvalidFileName {
+                        path: f.to_string(),
+                    });
+                    continue;
+                }
             };
-            fixture_files.push(fixture);
+            match File::open(f) {
+                Ok(content) => fixture_files.push(FixtureFile {
+                    path: f.to_string(),
+                    file_name,
+                    content,
+                    tables: vec![],
+                    insert_sqls: vec![],
+                }),
+                Err(err) => errors.push(Error::Io {
+                    path: f.to_string(),
+                    source: err,
+                }),
+            }
         }
-        fixture_files
+        (fixture_files, errors)
     }
 
     /// Set fixture file content from a directory to [FixtureFile](crate::fixture_file::FixtureFile) struct.
-    fn fixtures_from_directory(directory: &str) -> Vec<FixtureFile<Tz>> {
+    fn fixtures_from_directory(directory: &str) -> (Vec<FixtureFile<Tz>>, Vec<Error>) {
         let mut fixture_files: Vec<FixtureFile<Tz>> = vec![];
-        for f in fs::read_dir(directory).unwrap() {
-            let f = f.unwrap();
-            let file_extension = match f.path().extension() {
-                Some(ext) => ext.to_str().unwrap().to_string(),
-                None => "".to_string(),
+        let mut errors = vec![];
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(err) => {
+                errors.push(Error::Io {
+                    path: directory.to_string(),
+                    source: err,
+                });
+                return (fixture_files, errors);
+            }
+        };
+        for f in entries {
+            let f = match f {
+                Ok(f) => f,
+                Err(err) => {
+                    errors.push(Error::Io {
+                        path: directory.to_string(),
+                        source: err,
+                    });
+                    continue;
+                }
+            };
+            let path = f.path();
+            let file_extension = path.extension().and_then(|x| x.to_str()).unwrap_or("");
+            if path.is_dir() || (file_extension != "yml" && file_extension != "yaml") {
+                continue;
+            }
+            let (path, file_name) = match (path.to_str(), f.file_name().to_str()) {
+                (Some(path), Some(file_name)) => (path.to_string(), file_name.to_string()),
+                _ => {
+                    errors.push(Error::InvalidFileName {
+                        path: path.to_string_lossy().to_string(),
+                    });
+                    continue;
+                }
             };
-            if !f.path().is_dir() && (file_extension == "yml" || file_extension == "yaml") {
-                let fixture = FixtureFile {
-                    path: f.path().to_str().unwrap().to_string(),
-                    file_name: f.file_name().to_str().unwrap().to_string(),
-                    content: File::open(f.path()).unwrap(),
+            match File::open(&path) {
+                Ok(content) => fixture_files.push(FixtureFile {
+                    path,
+                    file_name,
+                    content,
+                    tables: vec![],
                     insert_sqls: vec![],
-                };
-                fixture_files.push(fixture);
+                }),
+                Err(err) => errors.push(Error::Io { path, source: err }),
             }
         }
-        fixture_files
+        fixture_files.sort_by(|a, b| a.path.cmp(&b.path));
+        (fixture_files, errors)
     }
 
     /// Set fixture file content from a directory to [FixtureFile](crate::fixture_file::FixtureFile) struct.
-    fn fixtures_from_paths(paths: Vec<&str>) -> Vec<FixtureFile<Tz>> {
+    fn fixtures_from_paths(paths: Vec<&str>) -> (Vec<FixtureFile<Tz>>, Vec<Error>) {
         let mut fixture_files: Vec<FixtureFile<Tz>> = vec![];
+        let mut errors = vec![];
         for path in paths {
-            if Path::new(path).is_dir() {
-                fixture_files.append(&mut Self::fixtures_from_directory(path))
+            let (mut fixtures, mut errs) = if Path::new(path).is_dir() {
+                Self::fixtures_from_directory(path)
             } else {
-                fixture_files.append(&mut Self::fixtures_from_files(vec![path]))
-            }
+                Self::fixtures_from_files(vec![path])
+            };
+            fixture_files.append(&mut fixtures);
+            errors.append(&mut errs);
+        }
+        (fixture_files, errors)
+    }
+
+    /// Read the schema with the helper and build SQL queries from fixture files.
+    /// The schema of a database which does not pass the test database check is not read,
+    /// so [load](Loader::load) returns [Error::NotATestDatabase] rather than errors about records.
+    pub(crate) async fn prepare(&mut self) -> Result<(), Error> {
+        if self.skip_test_database_check || self.ensure_test_database().await.is_ok() {
+            // Values are bound by the column types read by `init`.
+            self.helper
+                .as_mut()
+                .unwrap()
+                .init(self.pool.as_ref().unwrap())
+                .await?;
         }
-        fixture_files
+        self.build_insert_sqls()
     }
 
     /// Build SQL queries from fixture files.
-    pub(crate) fn build_insert_sqls(&mut self) {
-        for index in 0..self.fixture_files.len() {
-            let file = &self.fixture_files[index].content;
-            let mut buf_reader = BufReader::new(file);
+    /// Records are checked against the columns of their tables if the helper has read them.
+    /// Errors found while reading fixture files, and records which do not fit the schema, are returned all together.
+    pub(crate) fn build_insert_sqls(&mut self) -> Result<(), Error> {
+        let mut errors: Vec<Error> = self.errors.drain(..).collect();
+        let mut contents = vec![];
+        for fixture_file in &self.fixture_files {
+            let mut buf_reader = BufReader::new(&fixture_file.content);
             let mut content = String::new();
-            buf_reader.read_to_string(&mut content).unwrap();
-            let records = YamlLoader::load_from_str(content.as_str()).unwrap();
+            if let Err(err) = buf_reader.read_to_string(&mut content) {
+                errors.push(Error::Io {
+                    path: fixture_file.path.clone(),
+                    source: err,
+                });
+                continue;
+            }
+            match YamlLoader::load_from_str(content.as_str()) {
+                Ok(content) => contents.push(content),
+                Err(err) => errors.push(Error::FixtureParse {
+                    path: fixture_file.path.clone(),
+                    source: err,
+                }),
+            }
+        }
+        into_result(errors)?;
+        let mut errors = vec![];
+
+        // Records are referred to as `<table>.<record>`. A record in a list is named by its index.
+        let mut references = HashMap::new();
+        for (index, content) in contents.iter().enumerate() {
+            for (table, records) in Self::sections(&self.fixture_files[index], content.first()) {
+                for (name, record) in records {
+                    references.insert(format!("{}.{}", table, name), record);
+                }
+            }
+        }
 
-            if let Yaml::Array(records) = &records[0] {
-                for record in records {
-                    let (sql, values) = self.build_insert_sql(&self.fixture_files[index], record);
-                    self.fixture_files[index].insert_sqls.push(InsertSql {
+        let max_bind_params = self
+            .helper
+            .as_ref()
+            .map_or(u16::MAX as usize, |x| x.max_bind_params());
+        // Records are checked against the columns read by the helper, and all problems are returned together.
+        let schema = self.helper.as_ref().and_then(|x| x.schema());
+        for (index, content) in contents.iter().enumerate() {
+            let file = &self.fixture_files[index];
+            let mut tables = vec![];
+            let mut insert_sqls = vec![];
+            for (table, records) in Self::sections(file, content.first()) {
+                let table = match &self.schema {
+                    Some(schema) if !table.contains('.') => format!("{}.{}", schema, table),
+                    _ => table,
+                };
+                let table_columns = match schema.map(|x| x.get(&table)) {
+                    Some(Some(columns)) => Some(columns.as_slice()),
+                    Some(None) => {
+                        errors.push(Error::UnknownTable {
+                            path: file.path.clone(),
+                            table,
+                        });
+                        continue;
+                    }
+                    None => None,
+                };
+                // Consecutive records with the same columns are inserted by one statement.
+                let mut batches: Vec<Batch<Tz>> = vec![];
+                for (record_index, (name, record)) in records.into_iter().enumerate() {
+                    let (columns, values, mut params) = self.build_record(
+                        file,
+                        (&table, table_columns),
+                        record_index,
+                        &name,
+                        record,
+                        &references,
+                        &mut errors,
+                    );
+                    if let Some(table_columns) = table_columns {
+                        errors.append(&mut Self::validate_columns(
+                            file,
+                            &table,
+                            table_columns,
+                            record_index,
+                            &columns,
+                        ));
+                    }
+                    match batches.last_mut() {
+                        Some(batch)
+                            if batch.columns == columns
+                                && batch.params.len() + params.len() <= max_bind_params =>
+                        {
+                            batch.rows.push(values);
+                            batch.params.append(&mut params);
+                        }
+                        _ => batches.push(Batch {
+                            record_index,
+                            columns,
+                            rows: vec![values],
+                            params,
+                        }),
+                    }
+                }
+                for batch in batches {
+                    let mut param_index = 0;
+                    let rows = batch
+                        .rows
+                        .iter()
+                        .map(|values| {
+                            values
+                                .iter()
+                                .map(|value| match value {
+                                    SqlValue::Placeholder => {
+                                        param_index += 1;
+                                        Some(param_index - 1)
+                                    }
+                                    SqlValue::Raw(_) => None,
+                                })
+                                .collect()
+                        })
+                        .collect();
+                    let sql = match self.build_insert_sql(&table, &batch.columns, &batch.rows) {
+                        Ok(sql) => sql,
+                        Err(err) => {
+                            errors.push(err);
+                            continue;
+                        }
+                    };
+                    insert_sqls.push(InsertSql {
+                        table: table.clone(),
+                        record_index: batch.record_index,
                         sql,
-                        params: values,
+                        params: batch.params,
+                        columns: batch.columns,
+                        rows,
                     });
                 }
+                tables.push(table);
+            }
+            self.fixture_files[index].tables = tables;
+            self.fixture_files[index]
+                .insert_sqls
+                .append(&mut insert_sqls);
+        }
+        into_result(errors)
+    }
+
+    /// Check if a fixture file is a mapping from table names to lists of records.
+    fn is_multi_table(content: &Yaml) -> bool {
+        match content {
+            Yaml::Hash(sections) => {
+                !sections.is_empty() && sections.values().all(|x| x.as_vec().is_some())
+            }
+            _ => false,
+        }
+    }
+
+    /// Split a fixture file into tables and their records.
+    /// A multi-table file has a section per table. Any other file is a table named after the file.
+    fn sections<'a>(
+        file: &FixtureFile<Tz>,
+        content: Option<&'a Yaml>,
+    ) -> Vec<(String, Vec<(String, &'a Yaml)>)> {
+        match content {
+            Some(content @ Yaml::Hash(sections)) if Self::is_multi_table(content) => sections
+                .iter()
+                .map(|(table, records)| {
+                    let table = match table {
+                        Yaml::String(table) => table.to_string(),
+                        _ => format!("{:?}", table),
+                    };
+                    (table, Self::records(Some(records)))
+                })
+                .collect(),
+            _ => vec![(file.file_stem(), Self::records(content))],
+        }
+    }
+
+    /// List records of a fixture file with their names.
+    /// A file is either a list of records or a mapping from record names to records.
+    fn records(content: Option<&Yaml>) -> Vec<(String, &Yaml)> {
+        match content {
+            Some(Yaml::Array(records)) => records
+                .iter()
+                .enumerate()
+                .map(|(index, record)| (index.to_string(), record))
+                .collect(),
+            Some(Yaml::Hash(records)) => records
+                .iter()
+                .map(|(name, record)| match name {
+                    Yaml::String(name) => (name.to_string(), record),
+                    _ => (format!("{:?}", name), record),
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Resolve `$ref(<table>.<record>.<column>)` to the value of the referenced column.
+    /// Values which are not references are returned as they are.
+    fn resolve_reference<'a>(
+        path: &str,
+        name: &str,
+        value: &'a Yaml,
+        references: &HashMap<String, &'a Yaml>,
+    ) -> Result<&'a Yaml, Error> {
+        let mut resolved = value;
+        let mut visited = vec![];
+        loop {
+            let reference = match resolved {
+                Yaml::String(v) if v.starts_with("$ref(") && v.ends_with(')') => v,
+                _ => return Ok(resolved),
+            };
+            // A referenced value can be a reference too, but a cycle never ends.
+            if visited.contains(&reference) {
+                return Err(Error::CircularReference {
+                    path: path.to_string(),
+                    record: name.to_string(),
+                    reference: value.as_str().unwrap_or_default().to_string(),
+                });
+            }
+            visited.push(reference);
+            let mut parts = reference["$ref(".len()..reference.len() - 1].rsplitn(2, '.');
+            let column = Yaml::String(parts.next().unwrap_or_default().to_string());
+            let record = parts.next().and_then(|x| references.get(x));
+            resolved = match record
+                .and_then(|x| x.as_hash())
+                .and_then(|x| x.get(&column))
+            {
+                Some(value) => value,
+                None => {
+                    return Err(Error::UnresolvedReference {
+                        path: path.to_string(),
+                        record: name.to_string(),
+                        reference: reference.to_string(),
+                    })
+                }
             };
         }
     }
 
-    fn build_insert_sql(
+    /// Build columns, SQL values and parameters of the `record_index`th record of a table.
+    /// `table` is the name of the table and its columns if the helper has read them.
+    /// Values are converted to the types of their columns. Keys which are not column names, values which can not be converted,
+    /// and references which can not be resolved are added to `errors`. Such values are bound as NULL.
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
+    fn build_record(
         &self,
         file: &FixtureFile<Tz>,
+        table: (&str, Option<&[Column]>),
+        record_index: usize,
+        name: &str,
         record: &Yaml,
-    ) -> (String, Vec<SqlParam<Tz>>) {
+        references: &HashMap<String, &Yaml>,
+        errors: &mut Vec<Error>,
+    ) -> (Vec<String>, Vec<SqlValue>, Vec<SqlParam<Tz>>) {
+        let (table, table_columns) = table;
         let mut sql_columns = vec![];
         let mut sql_values = vec![];
         let mut values = vec![];
         if let Yaml::Hash(hash) = &record {
             for (key, value) in hash {
-                match key {
-                    Yaml::String(k) => sql_columns.push(k.to_string()),
-                    Yaml::Integer(k) => sql_columns.push(k.to_string()),
-                    _ => (),
+                let invalid = |column: String, reason: String| Error::InvalidRecord {
+                    path: file.path.clone(),
+                    table: table.to_string(),
+                    record_index,
+                    column,
+                    reason,
                 };
-                match value {
-                    Yaml::String(v) => {
-                        if v.starts_with("RAW=") {
-                            sql_values.push(v.replace("RAW=", ""));
-                            continue;
-                        } else {
-                            match self.try_str_to_date(v.to_string()) {
-                                Ok(datetime) => values.push(SqlParam::Datetime(datetime)),
-                                Err(_) => values.push(SqlParam::String(v.to_string())),
-                            }
-                        }
+                let column = match key {
+                    Yaml::String(k) => k.to_string(),
+                    Yaml::Integer(k) => k.to_string(),
+                    _ => {
+                        errors.push(invalid(
+                            format!("{:?}", key),
+                            "is not a column name".to_string(),
+                        ));
+                        continue;
+                    }
+                };
+                sql_columns.push(column.clone());
+                let value = match Self::resolve_reference(&file.path, name, value, references) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        errors.push(err);
+                        values.push(SqlParam::Null);
+                        sql_values.push(SqlValue::Placeholder);
+                        continue;
+                    }
+                };
+                if let Yaml::String(v) = value {
+                    if v.starts_with("RAW=") {
+                        sql_values.push(SqlValue::Raw(v.replace("RAW=", "")));
+                        continue;
+                    }
+                }
+                let table_column = table_columns.and_then(|x| x.iter().find(|x| x.name == column));
+                let param = match (value, table_column) {
+                    // `DECIMAL=` keeps the exact value whatever the type of the column is.
+                    (Yaml::String(v), _) if v.starts_with("DECIMAL=") => {
+                        Self::decimal(&v["DECIMAL=".len()..])
+                            .map(SqlParam::Decimal)
+                            .ok_or_else(|| "not a decimal number".to_string())
                     }
-                    Yaml::Integer(v) => values.push(SqlParam::Integer(*v as u32)),
-                    Yaml::Real(v) => values.push(SqlParam::Float(f32::from_str(v).unwrap())),
-                    Yaml::Boolean(v) => values.push(SqlParam::Boolean(*v)),
-                    _ => (),
+                    (value, Some(table_column)) => self.convert(value, table_column),
+                    (value, None) => self.guess(value).ok_or_else(|| "not a scalar".to_string()),
                 };
-                sql_values.push("?".to_string());
+                match param {
+                    Ok(param) => values.push(param),
+                    Err(reason) => {
+                        errors.push(invalid(
+                            column,
+                            format!(
+                                "can not store '{}': {}",
+                                yaml_text(value).unwrap_or_else(|| format!("{:?}", value)),
+                                reason
+                            ),
+                        ));
+                        // Every placeholder has a parameter, even in a record which is not inserted.
+                        values.push(SqlParam::Null);
+                    }
+                }
+                sql_values.push(SqlValue::Placeholder);
             }
         };
 
-        let sql_str = format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            file.file_stem(),
-            sql_columns.join(", "),
-            sql_values.join(", "),
-        );
-        (sql_str, values)
+        (sql_columns, sql_values, values)
     }
 
-    // Check if database name ends with test.
-    async fn ensure_test_database(&self) -> anyhow::Result<()> {
-        let db_name = self
-            .helper
-            .as_ref()
-            .unwrap()
-            .database_name(self.pool.as_ref().unwrap())
-            .await?;
-        let re = Regex::new(r"^*?test$")?;
-        if !re.is_match(db_name.as_str()) {
-            return Err(anyhow::anyhow!(
-                r#"'{}' does not appear to be a test database"#,
-                db_name
-            ));
+    /// Check the columns of the `record_index`th record of `table` against the columns of the table.
+    /// A column has to exist, and a NOT NULL column without a default can not be left out.
+    fn validate_columns(
+        file: &FixtureFile<Tz>,
+        table: &str,
+        table_columns: &[Column],
+        record_index: usize,
+        columns: &[String],
+    ) -> Vec<Error> {
+        let error = |column: &str, reason: &str| Error::InvalidRecord {
+            path: file.path.clone(),
+            table: table.to_string(),
+            record_index,
+            column: column.to_string(),
+            reason: reason.to_string(),
+        };
+        let unknown = columns
+            .iter()
+            .filter(|column| !table_columns.iter().any(|x| &&x.name == column))
+            .map(|column| error(column, "does not exist"));
+        let missing = table_columns
+            .iter()
+            .filter(|x| !x.nullable && !x.has_default && !columns.contains(&x.name))
+            .map(|x| {
+                error(
+                    &x.name,
+                    "is NOT NULL and has no default, but it is left out",
+                )
+            });
+        unknown.chain(missing).collect()
+    }
+
+    /// Convert a value to a parameter by the YAML type of the value.
+    /// Strings which look like datetimes are datetimes.
+    fn guess(&self, value: &Yaml) -> Option<SqlParam<Tz>> {
+        match value {
+            Yaml::String(v) => match self.try_str_to_date(v.to_string()) {
+                Ok(datetime) => Some(SqlParam::Datetime(datetime)),
+                Err(_) => Some(SqlParam::String(v.to_string())),
+            },
+            Yaml::Integer(v) => Some(SqlParam::Integer(*v)),
+            // yaml-rust reads integers larger than `i64::MAX` as reals.
+            real @ Yaml::Real(v) => match (u64::from_str(v), Self::decimal(v)) {
+                (Ok(v), _) => Some(SqlParam::UnsignedInteger(v)),
+                (_, Some(v)) => Some(SqlParam::Decimal(v)),
+                // `.inf` and `.nan` are not decimal numbers.
+                _ => Some(SqlParam::Float(real.as_f64().unwrap_or_default())),
+            },
+            Yaml::Boolean(v) => Some(SqlParam::Boolean(*v)),
+            Yaml::Null => Some(SqlParam::Null),
+            _ => None,
+        }
+    }
+
+    /// Convert a value to a parameter of the type of `column`.
+    /// Returns why the value does not fit in the column if it can not be converted.
+    fn convert(&self, value: &Yaml, column: &Column) -> Result<SqlParam<Tz>, String> {
+        if let Yaml::Null = value {
+            return match column.nullable {
+                true => Ok(SqlParam::Null),
+                false => Err("the column is NOT NULL".to_string()),
+            };
+        }
+        match &column.column_type {
+            ColumnType::Integer { min, max } => {
+                let integer = match value {
+                    Yaml::Integer(v) => Some(*v as i128),
+                    Yaml::Boolean(v) => Some(*v as i128),
+                    Yaml::Real(v) | Yaml::String(v) => i128::from_str(v).ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| "not an integer".to_string())?;
+                if integer < *min || integer > *max {
+                    return Err(format!("not an integer from {} to {}", min, max));
+                }
+                match i64::try_from(integer) {
+                    Ok(integer) => Ok(SqlParam::Integer(integer)),
+                    Err(_) => Ok(SqlParam::UnsignedInteger(integer as u64)),
+                }
+            }
+            ColumnType::Decimal => match value {
+                Yaml::Integer(v) => Some(v.to_string()),
+                Yaml::Real(v) | Yaml::String(v) => Self::decimal(v),
+                _ => None,
+            }
+            .map(SqlParam::Decimal)
+            .ok_or_else(|| "not a decimal number".to_string()),
+            ColumnType::Float => match value {
+                Yaml::Integer(v) => Some(*v as f64),
+                Yaml::Real(_) => value.as_f64(),
+                Yaml::String(v) => f64::from_str(v).ok(),
+                _ => None,
+            }
+            .map(SqlParam::Float)
+            .ok_or_else(|| "not a number".to_string()),
+            ColumnType::Boolean => match value {
+                Yaml::Boolean(v) => Some(*v),
+                Yaml::Integer(0) => Some(false),
+                Yaml::Integer(1) => Some(true),
+                _ => None,
+            }
+            .map(SqlParam::Boolean)
+            .ok_or_else(|| "not a boolean".to_string()),
+            ColumnType::Date => value
+                .as_str()
+                .and_then(|v| self.str_to_naive_date(v))
+                .map(SqlParam::Date)
+                .ok_or_else(|| "not a date".to_string()),
+            ColumnType::Time => value
+                .as_str()
+                .and_then(|v| {
+                    ["%H:%M:%S%.f", "%H:%M"]
+                        .iter()
+                        .find_map(|f| NaiveTime::parse_from_str(v, f).ok())
+                })
+                .map(SqlParam::Time)
+                .ok_or_else(|| "not a time".to_string()),
+            ColumnType::Datetime => value
+                .as_str()
+                .and_then(|v| match self.try_str_to_date(v.to_string()) {
+                    Ok(datetime) => Some(datetime),
+                    // A date is midnight of the day.
+                    Err(_) => self
+                        .location
+                        .as_ref()?
+                        .from_local_datetime(&self.str_to_naive_date(v)?.and_hms_opt(0, 0, 0)?)
+                        .single(),
+                })
+                .map(SqlParam::Datetime)
+                .ok_or_else(|| "not a datetime".to_string()),
+            ColumnType::Json => match value {
+                Yaml::String(v) => Some(v.to_string()),
+                value => yaml_to_json(value),
+            }
+            .map(SqlParam::Json)
+            .ok_or_else(|| "not representable in JSON".to_string()),
+            ColumnType::Binary => value
+                .as_str()
+                .map(|v| SqlParam::Binary(v.as_bytes().to_vec()))
+                .ok_or_else(|| "not a string".to_string()),
+            ColumnType::Enum(labels) => match yaml_text(value) {
+                Some(v) if labels.contains(&v) => Ok(SqlParam::Enum(v)),
+                _ => Err(format!("not one of {}", labels.join(", "))),
+            },
+            ColumnType::Text => match (yaml_text(value), column.max_length) {
+                (Some(v), Some(max_length)) if v.chars().count() > max_length => {
+                    Err(format!("longer than {} characters", max_length))
+                }
+                (Some(v), _) => Ok(SqlParam::String(v)),
+                (None, _) => Err("not a string".to_string()),
+            },
+            ColumnType::Other => self.guess(value).ok_or_else(|| "not a scalar".to_string()),
+        }
+    }
+
+    /// Try change str to date. A datetime is the date of it.
+    fn str_to_naive_date(&self, s: &str) -> Option<NaiveDate> {
+        ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d"]
+            .iter()
+            .find_map(|f| NaiveDate::parse_from_str(s, f).ok())
+            .or_else(|| {
+                self.try_str_to_date(s.to_string())
+                    .ok()
+                    .map(|x| x.naive_local().date())
+            })
+    }
+
+    /// Build an INSERT query for rows which have the same columns.
+    /// Identifiers and placeholders are rendered by the database helper.
+    fn build_insert_sql(
+        &self,
+        table: &str,
+        columns: &[String],
+        rows: &[Vec<SqlValue>],
+    ) -> Result<String, Error> {
+        let columns = columns
+            .iter()
+            .map(|x| self.quote_identifier(x))
+            .collect::<Result<Vec<String>, Error>>()?;
+        let mut index = 0;
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|values| {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| match value {
+                        SqlValue::Placeholder => {
+                            index += 1;
+                            self.helper
+                                .as_ref()
+                                .map_or("?".to_string(), |x| x.placeholder(index))
+                        }
+                        SqlValue::Raw(sql) => sql.clone(),
+                    })
+                    .collect();
+                format!("({})", values.join(", "))
+            })
+            .collect();
+        Ok(format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            quote_qualified(table, |x| self.quote_identifier(x))?,
+            columns.join(", "),
+            rows.join(", "),
+        ))
+    }
+
+    /// Quote a table or column name with the database helper.
+    /// Without a helper, names are left as they are.
+    fn quote_identifier(&self, identifier: &str) -> Result<String, Error> {
+        match self.helper.as_ref() {
+            Some(helper) => helper.quote_identifier(identifier),
+            None => Ok(identifier.to_string()),
+        }
+    }
+
+    /// Return `s` as it is if it is a decimal number.
+    fn decimal(s: &str) -> Option<String> {
+        BigDecimal::from_str(s).ok().map(|_| s.to_string())
+    }
+
+    /// Check that the database is a test database.
+    /// Every rule that is set has to pass. Without a pattern or an allowlist,
+    /// the database name has to end with `test`.
+    async fn ensure_test_database(&self) -> Result<(), Error> {
+        let helper = self.helper.as_ref().unwrap();
+        let pool = self.pool.as_ref().unwrap();
+        let name = helper.database_name(pool).await?;
+        let not_a_test_database = |rule: String| {
+            Err(Error::NotATestDatabase {
+                name: name.clone(),
+                rule,
+            })
+        };
+
+        for variable in &self.required_env_vars {
+            if env::var_os(variable).is_none() {
+                return not_a_test_database(format!(
+                    "environment variable {} is not set",
+                    variable
+                ));
+            }
+        }
+        if self.require_local_host {
+            match helper.server_address(pool).await? {
+                Some(address) if is_local_address(&address) => (),
+                Some(address) => {
+                    return not_a_test_database(format!(
+                        "host {} is not localhost or a private address",
+                        address
+                    ))
+                }
+                None => {
+                    return not_a_test_database(
+                        "could not determine the server address".to_string(),
+                    )
+                }
+            }
+        }
+        if let Some(pattern) = &self.test_database_pattern {
+            if !pattern.is_match(&name) {
+                return not_a_test_database(format!("name does not match /{}/", pattern));
+            }
+        }
+        if !self.allowed_databases.is_empty() && !self.allowed_databases.contains(&name) {
+            return not_a_test_database("name is not in the allowed databases".to_string());
+        }
+        if self.test_database_pattern.is_none() && self.allowed_databases.is_empty() {
+            let re = Regex::new(r"^*?test$").unwrap();
+            if !re.is_match(name.as_str()) {
+                return not_a_test_database("name does not end with test".to_string());
+            }
         }
         Ok(())
     }
 }
 
+/// A value in the VALUES clause of an INSERT query.
+enum SqlValue {
+    /// A bind parameter.
+    Placeholder,
+    /// A raw SQL written with `RAW=`.
+    Raw(String),
+}
+
+/// Records of a table which are inserted by one statement.
+struct Batch<Tz>
+where
+    Tz: TimeZone + Send + Sync,
+{
+    /// Index of the first record.
+    record_index: usize,
+    columns: Vec<String>,
+    /// SQL values of each record.
+    rows: Vec<Vec<SqlValue>>,
+    params: Vec<SqlParam<Tz>>,
+}
+
+/// Return the only error as it is, or several errors together.
+fn into_result(mut errors: Vec<Error>) -> Result<(), Error> {
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        _ => Err(Error::Fixtures(errors)),
+    }
+}
+
+/// Check if an address is localhost, a loopback address or a private address.
+/// The address can have a port, like `127.0.0.1:3306`.
+fn is_local_address(address: &str) -> bool {
+    let host = match address.parse::<IpAddr>() {
+        Ok(_) => address,
+        Err(_) => address.rsplit_once(':').map_or(address, |x| x.0),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host == "localhost" {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => match ip.to_ipv4_mapped() {
+            Some(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+            // fc00::/7 is for unique local addresses.
+            None => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+        },
+        Err(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::cleanup::Cleanup;
+    use crate::column_type::{Column, ColumnType};
+    use crate::error::Error;
     use crate::fixture_file::{FixtureFile, SqlParam};
     use crate::helper::Database as DB;
+    use crate::loader::is_local_address;
+    use crate::mysql::helper::MySql;
     use crate::mysql::loader::MySqlLoader;
     use async_trait::async_trait;
     use chrono::{prelude::*, Utc};
     use sqlx::{MySql as M, MySqlConnection, MySqlPool};
+    use std::collections::HashMap;
     use std::fs::File;
     use std::io::{prelude::*, BufReader, Write};
     use tempfile::{tempdir, TempDir};
     use yaml_rust::{Yaml, YamlLoader};
 
+    /// A helper which does not connect to a database. Its fields set what it returns.
+    struct MockDatabase {
+        database_name: String,
+        server_address: Option<String>,
+        max_bind_params: usize,
+    }
+
+    impl Default for MockDatabase {
+        fn default() -> Self {
+            MockDatabase {
+                database_name: "test".to_string(),
+                server_address: Some("127.0.0.1".to_string()),
+                max_bind_params: u16::MAX as usize,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl<O, Tz> DB<M, MySqlConnection, O, Tz> for MockDatabase
+    where
+        O: Offset + Sync + Send + 'static,
+        Tz: TimeZone<Offset = O> + Send + Sync + 'static,
+    {
+        async fn init(&mut self, _pool: &MySqlPool) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn database_name(&self, _pool: &MySqlPool) -> Result<String, Error> {
+            Ok(self.database_name.clone())
+        }
+
+        async fn server_address(&self, _pool: &MySqlPool) -> Result<Option<String>, Error> {
+            Ok(self.server_address.clone())
+        }
+
+        async fn table_names(&self, _pool: &MySqlPool) -> Result<Vec<String>, Error> {
+            Ok(vec![])
+        }
+
+        async fn fingerprint(
+            &self,
+            _pool: &MySqlPool,
+            _tables: &[String],
+        ) -> Result<String, Error> {
+            Ok(String::new())
+        }
+
+        async fn load_checksum(&self, _pool: &MySqlPool) -> Result<Option<String>, Error> {
+            Ok(None)
+        }
+
+        async fn save_checksum(&self, _pool: &MySqlPool, _checksum: &str) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn quote_identifier(&self, identifier: &str) -> Result<String, Error> {
+            Ok(identifier.to_string())
+        }
+
+        fn max_bind_params(&self) -> usize {
+            self.max_bind_params
+        }
+
+        async fn with_transaction(
+            &self,
+            _pool: &MySqlPool,
+            _fixture_files: &[FixtureFile<Tz>],
+            _cleanup: Cleanup,
+            _clean_all_tables: bool,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn execute(
+            &self,
+            _conn: &mut MySqlConnection,
+            _fixture_files: &[FixtureFile<Tz>],
+            _cleanup: Cleanup,
+            _clean_all_tables: bool,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
     #[cfg_attr(feature = "runtime-async-std", async_std::test)]
     #[cfg_attr(feature = "runtime-tokio", tokio::test)]
     async fn it_returns_ok() -> anyhow::Result<()> {
+        #[derive(Default)]
         pub struct TestLoadNormal {}
-        impl Default for TestLoadNormal {
-            fn default() -> Self {
-                TestLoadNormal {}
-            }
-        }
         #[async_trait]
         impl<O, Tz> DB<M, MySqlConnection, O, Tz> for TestLoadNormal
         where
             O: Offset + Sync + Send + 'static,
             Tz: TimeZone<Offset = O> + Send + Sync + 'static,
         {
-            async fn init(&mut self, _pool: &MySqlPool) -> anyhow::Result<()> {
+            async fn init(&mut self, _pool: &MySqlPool) -> Result<(), Error> {
                 Ok(())
             }
 
-            async fn database_name(&self, _pool: &MySqlPool) -> anyhow::Result<String> {
+            async fn database_name(&self, _pool: &MySqlPool) -> Result<String, Error> {
                 Ok("test".to_string())
             }
 
@@ -305,14 +1218,18 @@ mod tests {
                 &self,
                 _pool: &MySqlPool,
                 _fixture_files: &[FixtureFile<Tz>],
-            ) -> anyhow::Result<()> {
+                _cleanup: Cleanup,
+                _clean_all_tables: bool,
+            ) -> Result<(), Error> {
                 Ok(())
             }
         }
 
-        let mut loader = MySqlLoader::<Utc, Utc>::default();
-        loader.pool = Some(MySqlPool::new("fizz").await?);
-        loader.helper = Some(Box::new(TestLoadNormal {}));
+        let loader = MySqlLoader::<Utc, Utc> {
+            pool: Some(MySqlPool::new("fizz").await?),
+            helper: Some(Box::new(TestLoadNormal {})),
+            ..Default::default()
+        };
         let result = loader.load().await;
         assert!(result.is_ok());
         Ok(())
@@ -321,23 +1238,19 @@ mod tests {
     #[cfg_attr(feature = "runtime-async-std", async_std::test)]
     #[cfg_attr(feature = "runtime-tokio", tokio::test)]
     async fn it_returns_transaction_error() -> anyhow::Result<()> {
+        #[derive(Default)]
         pub struct TestLoadTransactionError {}
-        impl Default for TestLoadTransactionError {
-            fn default() -> Self {
-                TestLoadTransactionError {}
-            }
-        }
         #[async_trait]
         impl<O, Tz> DB<M, MySqlConnection, O, Tz> for TestLoadTransactionError
         where
             O: Offset + Sync + Send + 'static,
             Tz: TimeZone<Offset = O> + Send + Sync + 'static,
         {
-            async fn init(&mut self, _pool: &MySqlPool) -> anyhow::Result<()> {
+            async fn init(&mut self, _pool: &MySqlPool) -> Result<(), Error> {
                 Ok(())
             }
 
-            async fn database_name(&self, _pool: &MySqlPool) -> anyhow::Result<String> {
+            async fn database_name(&self, _pool: &MySqlPool) -> Result<String, Error> {
                 Ok("test".to_string())
             }
 
@@ -345,18 +1258,22 @@ mod tests {
                 &self,
                 _pool: &MySqlPool,
                 _fixture_files: &[FixtureFile<Tz>],
-            ) -> anyhow::Result<()> {
-                Err(anyhow::anyhow!("error"))
+                _cleanup: Cleanup,
+                _clean_all_tables: bool,
+            ) -> Result<(), Error> {
+                Err(Error::Database(sqlx::Error::PoolClosed))
             }
         }
 
-        let mut loader = MySqlLoader::<Utc, Utc>::default();
-        loader.pool = Some(MySqlPool::new("fizz").await?);
-        loader.helper = Some(Box::new(TestLoadTransactionError {}));
+        let loader = MySqlLoader::<Utc, Utc> {
+            pool: Some(MySqlPool::new("fizz").await?),
+            helper: Some(Box::new(TestLoadTransactionError {})),
+            ..Default::default()
+        };
         let result = loader.load().await;
         assert!(result.is_err());
         if let Err(err) = result {
-            assert_eq!(err.to_string(), "error");
+            assert!(matches!(err, Error::Database(sqlx::Error::PoolClosed)));
         }
         Ok(())
     }
@@ -364,23 +1281,19 @@ mod tests {
     #[cfg_attr(feature = "runtime-async-std", async_std::test)]
     #[cfg_attr(feature = "runtime-tokio", tokio::test)]
     async fn it_returns_dabatase_check_error() -> anyhow::Result<()> {
+        #[derive(Default)]
         pub struct TestLoadDatabaseCheckError {}
-        impl Default for TestLoadDatabaseCheckError {
-            fn default() -> Self {
-                TestLoadDatabaseCheckError {}
-            }
-        }
         #[async_trait]
         impl<O, Tz> DB<M, MySqlConnection, O, Tz> for TestLoadDatabaseCheckError
         where
             O: Offset + Sync + Send + 'static,
             Tz: TimeZone<Offset = O> + Send + Sync + 'static,
         {
-            async fn init(&mut self, _pool: &MySqlPool) -> anyhow::Result<()> {
+            async fn init(&mut self, _pool: &MySqlPool) -> Result<(), Error> {
                 Ok(())
             }
 
-            async fn database_name(&self, _pool: &MySqlPool) -> anyhow::Result<String> {
+            async fn database_name(&self, _pool: &MySqlPool) -> Result<String, Error> {
                 Ok("fizz".to_string())
             }
 
@@ -388,25 +1301,82 @@ mod tests {
                 &self,
                 _pool: &MySqlPool,
                 _fixture_files: &[FixtureFile<Tz>],
-            ) -> anyhow::Result<()> {
+                _cleanup: Cleanup,
+                _clean_all_tables: bool,
+            ) -> Result<(), Error> {
                 Ok(())
             }
         }
 
-        let mut loader = MySqlLoader::<Utc, Utc>::default();
-        loader.pool = Some(MySqlPool::new("fizz").await?);
-        loader.helper = Some(Box::new(TestLoadDatabaseCheckError {}));
+        let loader = MySqlLoader::<Utc, Utc> {
+            pool: Some(MySqlPool::new("fizz").await?),
+            helper: Some(Box::new(TestLoadDatabaseCheckError {})),
+            ..Default::default()
+        };
         let result = loader.load().await;
         assert!(result.is_err());
         if let Err(err) = result {
             assert_eq!(
                 err.to_string(),
-                r#"testfixtures: 'fizz' does not appear to be a test database"#
+                r#"testfixtures: 'fizz' does not appear to be a test database: name does not end with test"#
             );
         }
         Ok(())
     }
 
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_checksum() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          created_at: 2020-01-01 01:01:01"#
+        )?;
+        let pool = MySqlPool::new("fizz").await?;
+        let checksum = |location: FixedOffset, cleanup: Cleanup, schema: Option<&str>| {
+            let mut loader = MySqlLoader::<FixedOffset, FixedOffset> {
+                pool: Some(pool.clone()),
+                helper: Some(Box::new(MockDatabase::default())),
+                ..Default::default()
+            };
+            loader.location(location);
+            loader.cleanup(cleanup);
+            if let Some(schema) = schema {
+                loader.schema(schema);
+            }
+            loader.files(vec![file_path.to_str().unwrap()]);
+            async move {
+                loader.build_insert_sqls()?;
+                loader.checksum().await
+            }
+        };
+
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let checksum_in_utc = checksum(utc, Cleanup::Delete, None).await?;
+        // The checksum is saved in the database, so it must not change between builds.
+        assert_eq!(
+            checksum_in_utc,
+            "1db6d0a86df73f7f5382c7cbc3d79bf9354381d026c484f1fb7501ff5e2f6f6f"
+        );
+        for other in [
+            checksum(
+                FixedOffset::east_opt(9 * 3600).unwrap(),
+                Cleanup::Delete,
+                None,
+            )
+            .await?,
+            checksum(utc, Cleanup::Truncate, None).await?,
+            checksum(utc, Cleanup::Delete, Some("app")).await?,
+        ] {
+            assert_ne!(other, checksum_in_utc);
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_location() {
         let mut loader = MySqlLoader::<Utc, Utc>::default();
@@ -431,6 +1401,20 @@ mod tests {
         assert!(loader.skip_test_database_check);
     }
 
+    #[test]
+    fn test_clean_all_tables() {
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.clean_all_tables();
+        assert!(loader.clean_all_tables);
+    }
+
+    #[test]
+    fn test_schema() {
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.schema("billing");
+        assert_eq!(loader.schema, Some("billing".to_string()));
+    }
+
     #[test]
     fn test_files() {
         let dir = tempdir().unwrap();
@@ -522,7 +1506,7 @@ mod tests {
         struct Test {
             argument: String,
             want_err: bool,
-        };
+        }
         let tests: [Test; 10] = [
             Test {
                 argument: "2020-01-01 01:01:01".to_string(),
@@ -582,6 +1566,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_convert() -> anyhow::Result<()> {
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        let yaml = |s: &str| YamlLoader::load_from_str(s).unwrap().remove(0);
+        let column = |column_type| Column {
+            name: "value".to_string(),
+            column_type,
+            nullable: true,
+            has_default: false,
+            max_length: None,
+        };
+
+        assert!(matches!(
+            loader.convert(&yaml("2020/01/01 01:01"), &column(ColumnType::Text)),
+            Ok(SqlParam::String(v)) if v == "2020/01/01 01:01"
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("255"), &column(ColumnType::unsigned(8))),
+            Ok(SqlParam::Integer(255))
+        ));
+        assert_eq!(
+            loader
+                .convert(&yaml("256"), &column(ColumnType::unsigned(8)))
+                .err(),
+            Some("not an integer from 0 to 255".to_string())
+        );
+        assert!(matches!(
+            loader.convert(
+                &yaml("18446744073709551615"),
+                &column(ColumnType::unsigned(64))
+            ),
+            Ok(SqlParam::UnsignedInteger(u64::MAX))
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("'19.99'"), &column(ColumnType::Decimal)),
+            Ok(SqlParam::Decimal(v)) if v == "19.99"
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("2020/01/31"), &column(ColumnType::Date)),
+            Ok(SqlParam::Date(v)) if v == NaiveDate::from_ymd_opt(2020, 1, 31).unwrap()
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("'09:30:15'"), &column(ColumnType::Time)),
+            Ok(SqlParam::Time(v)) if v == NaiveTime::from_hms_opt(9, 30, 15).unwrap()
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("2020-01-31"), &column(ColumnType::Datetime)),
+            Ok(SqlParam::Datetime(v)) if v == Utc.with_ymd_and_hms(2020, 1, 31, 0, 0, 0).unwrap()
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("{a: [1, true]}"), &column(ColumnType::Json)),
+            Ok(SqlParam::Json(v)) if v == r#"{"a":[1,true]}"#
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("low"), &column(ColumnType::Enum(vec!["low".to_string()]))),
+            Ok(SqlParam::Enum(v)) if v == "low"
+        ));
+        assert!(loader
+            .convert(
+                &yaml("high"),
+                &column(ColumnType::Enum(vec!["low".to_string()]))
+            )
+            .is_err());
+        assert!(loader
+            .convert(&yaml("fizz"), &column(ColumnType::Date))
+            .is_err());
+        assert!(matches!(
+            loader.convert(&yaml("~"), &column(ColumnType::Date)),
+            Ok(SqlParam::Null)
+        ));
+        assert!(matches!(
+            loader.convert(&yaml("2020/01/01 01:01"), &column(ColumnType::Other)),
+            Ok(SqlParam::Datetime(_))
+        ));
+
+        let not_null = Column {
+            nullable: false,
+            max_length: Some(4),
+            ..column(ColumnType::Text)
+        };
+        assert_eq!(
+            loader.convert(&yaml("~"), &not_null).err(),
+            Some("the column is NOT NULL".to_string())
+        );
+        assert_eq!(
+            loader.convert(&yaml("buzzz"), &not_null).err(),
+            Some("longer than 4 characters".to_string())
+        );
+        assert!(loader.convert(&yaml("fizz"), &not_null).is_ok());
+        Ok(())
+    }
+
     #[test]
     fn test_fixtures_from_files() {
         let dir = tempdir().unwrap();
@@ -597,8 +1674,9 @@ mod tests {
           updated_at: RAW=NOW()"#
         )
         .unwrap();
-        let fixture_files =
+        let (fixture_files, errors) =
             MySqlLoader::<Utc, Utc>::fixtures_from_files(vec![fixture_file_path.to_str().unwrap()]);
+        assert!(errors.is_empty());
         assert_eq!(
             fixture_files[0].file_name,
             fixture_file_path.file_name().unwrap().to_str().unwrap()
@@ -622,8 +1700,9 @@ mod tests {
           updated_at: RAW=NOW()"#
         )
         .unwrap();
-        let fixture_files =
+        let (fixture_files, errors) =
             MySqlLoader::<Utc, Utc>::fixtures_from_directory(dir.path().to_str().unwrap());
+        assert!(errors.is_empty());
         assert_eq!(fixture_files.len(), 1);
         assert_eq!(fixture_files[0].file_name, "todos.yml");
         Ok(())
@@ -654,20 +1733,155 @@ mod tests {
           updated_at: RAW=NOW()"#
         )
         .unwrap();
-        let fixture_files = MySqlLoader::<Utc, Utc>::fixtures_from_paths(vec![
+        let (fixture_files, errors) = MySqlLoader::<Utc, Utc>::fixtures_from_paths(vec![
             dir.path().to_str().unwrap(),
             dir.path().join("test_2.yml").to_str().unwrap(),
         ]);
-        assert_eq!(fixture_files[0].file_name, "test_2.yml");
-        assert_eq!(
-            fixture_files[1].file_name,
-            dir.path()
-                .join("test_1.yml")
-                .file_name()
-                .unwrap()
-                .to_str()
-                .unwrap()
+        assert!(errors.is_empty());
+        assert_eq!(fixture_files.len(), 3);
+        assert_eq!(fixture_files[0].file_name, "test_1.yml");
+        assert_eq!(fixture_files[1].file_name, "test_2.yml");
+        assert_eq!(fixture_files[2].file_name, "test_2.yml");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixtures_from_paths_with_missing_paths() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let missing_file = dir.path().join("missing.yml");
+        let missing_directory = dir.path().join("missing");
+        let (fixture_files, errors) =
+            MySqlLoader::<Utc, Utc>::fixtures_from_files(vec![missing_file.to_str().unwrap()]);
+        assert!(fixture_files.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert!(
+            matches!(&errors[0], Error::Io { path, .. } if path == missing_file.to_str().unwrap())
+        );
+
+        let (fixture_files, errors) =
+            MySqlLoader::<Utc, Utc>::fixtures_from_directory(missing_directory.to_str().unwrap());
+        assert!(fixture_files.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert!(
+            matches!(&errors[0], Error::Io { path, .. } if path == missing_directory.to_str().unwrap())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_invalid_yaml() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+- id: 1
+  description: [fizz"#
+        )?;
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        let err = loader.build_insert_sqls().unwrap_err();
+        assert!(
+            matches!(&err, Error::FixtureParse { path, .. } if path == file_path.to_str().unwrap())
         );
+        let message = err.to_string();
+        assert!(message.starts_with(&format!(
+            "testfixtures: failed to parse {}: ",
+            file_path.to_str().unwrap()
+        )));
+        assert!(message.contains(" at line "), "{}", message);
+        assert!(message.contains(" column "), "{}", message);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_multiple_errors() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(file, "- id: [1")?;
+        let missing_file = dir.path().join("missing.yml");
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![
+            missing_file.to_str().unwrap(),
+            file_path.to_str().unwrap(),
+        ]);
+        match loader.build_insert_sqls() {
+            Err(Error::Fixtures(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert!(matches!(errors[0], Error::Io { .. }));
+                assert!(matches!(errors[1], Error::FixtureParse { .. }));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_invalid_columns() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          tags: [fizz, buzz]
+          [1, 2]: fizz"#
+        )?;
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        match loader.build_insert_sqls() {
+            Err(Error::Fixtures(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert_eq!(
+                    errors[0].to_string(),
+                    format!(
+                        "testfixtures: record 0 of todos in {}: column tags can not store 'Array([String(\"fizz\"), String(\"buzz\")])': not a scalar",
+                        file_path.to_str().unwrap()
+                    )
+                );
+                assert_eq!(
+                    errors[1].to_string(),
+                    format!(
+                        "testfixtures: record 0 of todos in {}: column Array([Integer(1), Integer(2)]) is not a column name",
+                        file_path.to_str().unwrap()
+                    )
+                );
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_invalid_identifier() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          "": fizz
+          tags: [fizz, buzz]"#
+        )?;
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.helper = Some(Box::new(MySql::default()));
+        loader.files(vec![file_path.to_str().unwrap()]);
+        match loader.build_insert_sqls() {
+            Err(Error::Fixtures(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert!(matches!(errors[0], Error::InvalidRecord { .. }));
+                assert!(matches!(errors[1], Error::InvalidIdentifier { .. }));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
         Ok(())
     }
 
@@ -701,6 +1915,7 @@ mod tests {
                 .unwrap()
                 .to_string(),
             content: File::open(fixture_file_path).unwrap(),
+            tables: vec![],
             insert_sqls: vec![],
         };
         let mut buf_reader = BufReader::new(&fixture_file.content);
@@ -708,7 +1923,18 @@ mod tests {
         buf_reader.read_to_string(&mut contents).unwrap();
         let records = YamlLoader::load_from_str(contents.as_str()).unwrap();
         if let Yaml::Array(records) = &records[0] {
-            let (sql_str, values) = loader.build_insert_sql(&fixture_file, &records[0]);
+            let (columns, sql_values, values) = loader.build_record(
+                &fixture_file,
+                ("todos", None),
+                0,
+                "0",
+                &records[0],
+                &HashMap::new(),
+                &mut vec![],
+            );
+            let sql_str = loader
+                .build_insert_sql(fixture_file.file_stem().as_str(), &columns, &[sql_values])
+                .unwrap();
             assert_eq!(sql_str, format!("INSERT INTO {} (id, description, price, created_at, updated_at) VALUES (?, ?, ?, ?, NOW())", fixture_file.file_stem()));
             assert_eq!(values.len(), 4);
             if let SqlParam::Integer(param) = &values[0] {
@@ -717,35 +1943,429 @@ mod tests {
             if let SqlParam::String(param) = &values[1] {
                 assert_eq!(*param, "fizz".to_string())
             }
-            if let SqlParam::Float(param) = &values[2] {
-                assert_eq!(*param, 1.1)
-            }
+            assert!(matches!(&values[2], SqlParam::Decimal(v) if v == "1.1"));
             if let SqlParam::Datetime(param) = &values[3] {
-                assert_eq!(*param, Utc.ymd(2020, 1, 1).and_hms(1, 1, 1))
+                assert_eq!(*param, Utc.with_ymd_and_hms(2020, 1, 1, 1, 1, 1).unwrap())
             }
         }
     }
 
+    #[test]
+    fn test_build_insert_sql_with_numbers() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("ledgers.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 18446744073709551615
+          user_id: 9223372036854775807
+          amount: -4294967297
+          rate: 0.1234567890123"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        loader.build_insert_sqls()?;
+        let params = &loader.fixture_files[0].insert_sqls[0].params;
+        assert!(matches!(params[0], SqlParam::UnsignedInteger(u64::MAX)));
+        assert!(matches!(params[1], SqlParam::Integer(i64::MAX)));
+        assert!(matches!(params[2], SqlParam::Integer(-4294967297)));
+        match &params[3] {
+            SqlParam::Decimal(param) => assert_eq!(param, "0.1234567890123"),
+            _ => panic!("rate is not a decimal"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sql_with_decimals() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("invoices.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - amount: 19.99
+          tax: 1.50
+          total: DECIMAL=21.49
+          ratio: .inf"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        loader.build_insert_sqls()?;
+        let params = &loader.fixture_files[0].insert_sqls[0].params;
+        assert!(matches!(&params[0], SqlParam::Decimal(param) if param == "19.99"));
+        assert!(matches!(&params[1], SqlParam::Decimal(param) if param == "1.50"));
+        assert!(matches!(&params[2], SqlParam::Decimal(param) if param == "21.49"));
+        assert!(matches!(params[3], SqlParam::Float(param) if param == f64::INFINITY));
+
+        let mut file = File::create(&file_path)?;
+        writeln!(file, "- total: DECIMAL=fizz")?;
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        let err = loader.build_insert_sqls().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "testfixtures: record 0 of invoices in {}: column total can not store 'DECIMAL=fizz': not a decimal number",
+                file_path.to_str().unwrap()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sql_with_null() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          parent_id: ~
+          deleted_at: null"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        loader.build_insert_sqls()?;
+        let insert_sql = &loader.fixture_files[0].insert_sqls[0];
+        assert_eq!(
+            insert_sql.sql,
+            "INSERT INTO todos (id, parent_id, deleted_at) VALUES (?, ?, ?)"
+        );
+        assert_eq!(insert_sql.params.len(), 3);
+        assert!(matches!(insert_sql.params[1], SqlParam::Null));
+        assert!(matches!(insert_sql.params[2], SqlParam::Null));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_multiple_tables() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("scenario_checkout.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        users:
+          - id: 1
+            name: alice
+        posts:
+          - id: 1
+            user_id: 1
+          - id: 2
+            user_id: 1
+        comments: []"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        loader.build_insert_sqls()?;
+        let fixture_file = &loader.fixture_files[0];
+        assert_eq!(fixture_file.tables, vec!["users", "posts", "comments"]);
+        let insert_sqls: Vec<(&str, usize, &str)> = fixture_file
+            .insert_sqls
+            .iter()
+            .map(|x| (x.table.as_str(), x.record_index, x.sql.as_str()))
+            .collect();
+        assert_eq!(
+            insert_sqls,
+            vec![
+                ("users", 0, "INSERT INTO users (id, name) VALUES (?, ?)"),
+                (
+                    "posts",
+                    0,
+                    "INSERT INTO posts (id, user_id) VALUES (?, ?), (?, ?)"
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_build_insert_sqls_in_batches() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          description: fizz
+        - id: 2
+          description: buzz
+        - id: 3
+          description: fizzbuzz
+        - id: 4
+        - id: 5
+          description: RAW=UPPER('fizz')"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc> {
+            helper: Some(Box::new(MockDatabase {
+                max_bind_params: 4,
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        loader.build_insert_sqls()?;
+        let insert_sqls: Vec<(usize, &str, usize)> = loader.fixture_files[0]
+            .insert_sqls
+            .iter()
+            .map(|x| (x.record_index, x.sql.as_str(), x.params.len()))
+            .collect();
+        assert_eq!(
+            insert_sqls,
+            vec![
+                (
+                    0,
+                    "INSERT INTO todos (id, description) VALUES (?, ?), (?, ?)",
+                    4
+                ),
+                (2, "INSERT INTO todos (id, description) VALUES (?, ?)", 2),
+                (3, "INSERT INTO todos (id) VALUES (?)", 1),
+                (
+                    4,
+                    "INSERT INTO todos (id, description) VALUES (?, UPPER('fizz'))",
+                    1
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_schema() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let mut invoices = File::create(dir.path().join("billing.invoices.yml"))?;
+        writeln!(invoices, "- id: 1")?;
+        let mut todos = File::create(dir.path().join("todos.yml"))?;
+        writeln!(todos, "- id: 1")?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.directory(dir.path().to_str().unwrap());
+        loader.build_insert_sqls()?;
+        assert_eq!(loader.fixture_files[0].tables, vec!["billing.invoices"]);
+        assert_eq!(
+            loader.fixture_files[0].insert_sqls[0].sql,
+            "INSERT INTO billing.invoices (id) VALUES (?)"
+        );
+        assert_eq!(loader.fixture_files[1].tables, vec!["todos"]);
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.schema("archive");
+        loader.directory(dir.path().to_str().unwrap());
+        loader.build_insert_sqls()?;
+        assert_eq!(loader.fixture_files[0].tables, vec!["billing.invoices"]);
+        assert_eq!(loader.fixture_files[1].tables, vec!["archive.todos"]);
+        assert_eq!(
+            loader.fixture_files[1].insert_sqls[0].sql,
+            "INSERT INTO archive.todos (id) VALUES (?)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_reference() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let mut users = File::create(dir.path().join("users.yml"))?;
+        writeln!(
+            users,
+            r#"
+        alice:
+          id: 10
+          name: alice
+        bob:
+          id: 20
+          name: bob"#
+        )?;
+        let mut todos = File::create(dir.path().join("todos.yml"))?;
+        writeln!(
+            todos,
+            r#"
+        - id: 1
+          user_id: $ref(users.bob.id)
+        - id: 2
+          user_id: $ref(users.alice.id)"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.directory(dir.path().to_str().unwrap());
+        loader.build_insert_sqls()?;
+
+        assert_eq!(loader.fixture_files[0].file_name, "todos.yml");
+        let insert_sqls = &loader.fixture_files[0].insert_sqls;
+        assert_eq!(insert_sqls.len(), 1);
+        assert_eq!(
+            insert_sqls[0].sql,
+            "INSERT INTO todos (id, user_id) VALUES (?, ?), (?, ?)"
+        );
+        if let SqlParam::Integer(param) = &insert_sqls[0].params[1] {
+            assert_eq!(*param, 20)
+        } else {
+            panic!("user_id is not an integer")
+        }
+        if let SqlParam::Integer(param) = &insert_sqls[0].params[3] {
+            assert_eq!(*param, 10)
+        } else {
+            panic!("user_id is not an integer")
+        }
+
+        assert_eq!(loader.fixture_files[1].file_name, "users.yml");
+        let insert_sqls = &loader.fixture_files[1].insert_sqls;
+        assert_eq!(insert_sqls.len(), 1);
+        assert_eq!(
+            insert_sqls[0].sql,
+            "INSERT INTO users (id, name) VALUES (?, ?), (?, ?)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_reference_in_multi_table_file() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let mut scenario = File::create(dir.path().join("scenario.yml"))?;
+        writeln!(
+            scenario,
+            r#"
+        users:
+          - id: 10
+            name: alice
+          - id: 20
+            name: bob
+        todos:
+          - id: 1
+            user_id: $ref(users.1.id)"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.directory(dir.path().to_str().unwrap());
+        loader.build_insert_sqls()?;
+
+        let insert_sqls = &loader.fixture_files[0].insert_sqls;
+        assert_eq!(insert_sqls.len(), 2);
+        assert_eq!(
+            insert_sqls[1].sql,
+            "INSERT INTO todos (id, user_id) VALUES (?, ?)"
+        );
+        if let SqlParam::Integer(param) = &insert_sqls[1].params[1] {
+            assert_eq!(*param, 20)
+        } else {
+            panic!("user_id is not an integer")
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls_with_unresolved_reference() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        first:
+          id: 1
+          user_id: $ref(users.carol.id)
+        second:
+          id: 2
+          parent_id: $ref(todos.second.parent_id)"#
+        )?;
+
+        let mut loader = MySqlLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.files(vec![file_path.to_str().unwrap()]);
+        let result = loader.build_insert_sqls();
+        assert!(result.is_err());
+        if let Err(Error::Fixtures(errors)) = result {
+            assert_eq!(errors.len(), 2);
+            assert_eq!(
+                errors[0].to_string(),
+                format!(
+                    "testfixtures: unresolved reference '$ref(users.carol.id)' in record 'first' of {}",
+                    file_path.to_str().unwrap()
+                )
+            );
+            assert!(matches!(errors[1], Error::CircularReference { .. }));
+        } else {
+            panic!("expected several errors");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_reference() {
+        let records = YamlLoader::load_from_str(
+            r#"
+        alice:
+          id: 1
+          self_id: $ref(users.alice.self_id)
+          other_id: $ref(users.alice.id)"#,
+        )
+        .unwrap();
+        let alice = &records[0]["alice"];
+        let mut references = HashMap::new();
+        references.insert("users.alice".to_string(), alice);
+
+        let value = Yaml::String("$ref(users.alice.other_id)".to_string());
+        let result =
+            MySqlLoader::<Utc, Utc>::resolve_reference("users.yml", "alice", &value, &references);
+        assert_eq!(result.unwrap(), &Yaml::Integer(1));
+
+        let value = Yaml::String("fizz".to_string());
+        let result =
+            MySqlLoader::<Utc, Utc>::resolve_reference("users.yml", "alice", &value, &references);
+        assert_eq!(result.unwrap(), &value);
+
+        let value = Yaml::String("$ref(users.alice.name)".to_string());
+        let result =
+            MySqlLoader::<Utc, Utc>::resolve_reference("users.yml", "alice", &value, &references);
+        assert!(result.is_err());
+
+        let value = Yaml::String("$ref(users.alice.self_id)".to_string());
+        let result =
+            MySqlLoader::<Utc, Utc>::resolve_reference("users.yml", "alice", &value, &references);
+        assert!(result.is_err());
+        if let Err(err) = result {
+            assert_eq!(
+                err.to_string(),
+                "testfixtures: circular reference '$ref(users.alice.self_id)' in record 'alice' of users.yml"
+            );
+        }
+    }
+
     #[cfg_attr(feature = "runtime-async-std", async_std::test)]
     #[cfg_attr(feature = "runtime-tokio", tokio::test)]
     async fn test_ensure_test_database() -> anyhow::Result<()> {
+        #[derive(Default)]
         pub struct TestEnsureTestDatabaseNormal {}
-        impl Default for TestEnsureTestDatabaseNormal {
-            fn default() -> Self {
-                TestEnsureTestDatabaseNormal {}
-            }
-        }
         #[async_trait]
         impl<O, Tz> DB<M, MySqlConnection, O, Tz> for TestEnsureTestDatabaseNormal
         where
             O: Offset + Sync + Send + 'static,
             Tz: TimeZone<Offset = O> + Send + Sync + 'static,
         {
-            async fn init(&mut self, _pool: &MySqlPool) -> anyhow::Result<()> {
+            async fn init(&mut self, _pool: &MySqlPool) -> Result<(), Error> {
                 Ok(())
             }
 
-            async fn database_name(&self, _pool: &MySqlPool) -> anyhow::Result<String> {
+            async fn database_name(&self, _pool: &MySqlPool) -> Result<String, Error> {
                 Ok("test".to_string())
             }
 
@@ -753,33 +2373,32 @@ mod tests {
                 &self,
                 _pool: &MySqlPool,
                 _fixture_files: &[FixtureFile<Tz>],
-            ) -> anyhow::Result<()> {
+                _cleanup: Cleanup,
+                _clean_all_tables: bool,
+            ) -> Result<(), Error> {
                 Ok(())
             }
         }
-        let mut loader = MySqlLoader::<Utc, Utc>::default();
-        loader.pool = Some(MySqlPool::new("fizz").await?);
-        loader.helper = Some(Box::new(TestEnsureTestDatabaseNormal {}));
-        let result = loader.ensure_test_database().await?;
-        assert_eq!(result, ());
+        let loader = MySqlLoader::<Utc, Utc> {
+            pool: Some(MySqlPool::new("fizz").await?),
+            helper: Some(Box::new(TestEnsureTestDatabaseNormal {})),
+            ..Default::default()
+        };
+        assert!(loader.ensure_test_database().await.is_ok());
 
+        #[derive(Default)]
         pub struct TestEnsureTestDatabaseError {}
-        impl Default for TestEnsureTestDatabaseError {
-            fn default() -> Self {
-                TestEnsureTestDatabaseError {}
-            }
-        }
         #[async_trait]
         impl<O, Tz> DB<M, MySqlConnection, O, Tz> for TestEnsureTestDatabaseError
         where
             O: Offset + Sync + Send + 'static,
             Tz: TimeZone<Offset = O> + Send + Sync + 'static,
         {
-            async fn init(&mut self, _pool: &MySqlPool) -> anyhow::Result<()> {
+            async fn init(&mut self, _pool: &MySqlPool) -> Result<(), Error> {
                 Ok(())
             }
 
-            async fn database_name(&self, _pool: &MySqlPool) -> anyhow::Result<String> {
+            async fn database_name(&self, _pool: &MySqlPool) -> Result<String, Error> {
                 Ok("fizz".to_string())
             }
 
@@ -787,16 +2406,93 @@ mod tests {
                 &self,
                 _pool: &MySqlPool,
                 _fixture_files: &[FixtureFile<Tz>],
-            ) -> anyhow::Result<()> {
+                _cleanup: Cleanup,
+                _clean_all_tables: bool,
+            ) -> Result<(), Error> {
                 Ok(())
             }
         }
-        let mut loader = MySqlLoader::<Utc, Utc>::default();
-        loader.pool = Some(MySqlPool::new("fizz").await?);
-        loader.helper = Some(Box::new(TestEnsureTestDatabaseError {}));
+        let loader = MySqlLoader::<Utc, Utc> {
+            pool: Some(MySqlPool::new("fizz").await?),
+            helper: Some(Box::new(TestEnsureTestDatabaseError {})),
+            ..Default::default()
+        };
         let result = loader.ensure_test_database().await;
         assert!(result.is_err());
 
+        let mut loader = MySqlLoader::<Utc, Utc> {
+            pool: Some(MySqlPool::new("fizz").await?),
+            helper: Some(Box::new(MockDatabase {
+                database_name: "fizz".to_string(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        loader.test_database_pattern("^fi");
+        loader.require_local_host();
+        assert!(loader.ensure_test_database().await.is_ok());
+
+        for (server_address, expected) in [
+            (
+                Some("203.0.113.1"),
+                "host 203.0.113.1 is not localhost or a private address",
+            ),
+            (None, "could not determine the server address"),
+        ] {
+            let mut loader = MySqlLoader::<Utc, Utc> {
+                pool: Some(MySqlPool::new("fizz").await?),
+                helper: Some(Box::new(MockDatabase {
+                    server_address: server_address.map(|x| x.to_string()),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            };
+            loader.require_local_host();
+            match loader.ensure_test_database().await {
+                Err(Error::NotATestDatabase { rule, .. }) => assert_eq!(rule, expected),
+                _ => panic!("expected a test database check error"),
+            }
+        }
+
+        loader.allow_databases(vec!["buzz"]);
+        match loader.ensure_test_database().await {
+            Err(Error::NotATestDatabase { name, rule }) => {
+                assert_eq!(name, "fizz");
+                assert_eq!(rule, "name is not in the allowed databases");
+            }
+            _ => panic!("expected a test database check error"),
+        }
+
+        loader.allowed_databases = vec![];
+        loader.require_env("TESTFIXTURES_UNSET_VARIABLE");
+        match loader.ensure_test_database().await {
+            Err(Error::NotATestDatabase { rule, .. }) => assert_eq!(
+                rule,
+                "environment variable TESTFIXTURES_UNSET_VARIABLE is not set"
+            ),
+            _ => panic!("expected a test database check error"),
+        }
+
+        loader.test_database_pattern("(");
+        assert!(matches!(
+            loader.errors.as_slice(),
+            [Error::InvalidPattern { .. }]
+        ));
         Ok(())
     }
+
+    #[test]
+    fn test_is_local_address() {
+        assert!(is_local_address("localhost"));
+        assert!(is_local_address("127.0.0.1"));
+        assert!(is_local_address("172.17.0.2:3306"));
+        assert!(is_local_address("192.168.1.10"));
+        assert!(is_local_address("::1"));
+        assert!(is_local_address("[::1]:5432"));
+        assert!(is_local_address("::ffff:10.0.0.1"));
+        assert!(is_local_address("fd00::1"));
+        assert!(!is_local_address("203.0.113.1"));
+        assert!(!is_local_address("db.example.com:5432"));
+        assert!(!is_local_address("2001:db8::1"));
+    }
 }
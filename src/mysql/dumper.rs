@@ -0,0 +1,154 @@
+use crate::dumper::{split_table, Dump};
+use crate::error::Error;
+use crate::helper::{quote_identifier, quote_table};
+use async_trait::async_trait;
+use sqlx::cursor::Cursor;
+use sqlx::mysql::MySqlQueryAs;
+use sqlx::{MySqlPool, Row};
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+
+/// The character to quote identifiers.
+const QUOTE: char = '`';
+
+#[async_trait]
+impl Dump for MySqlPool {
+    async fn records(&self, table: &str) -> Result<Vec<Yaml>, Error> {
+        let (schema, name) = split_table(table);
+        let columns: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT column_name, data_type
+            FROM information_schema.columns
+            WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?
+            ORDER BY ordinal_position
+        "#,
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_all(self)
+        .await?;
+        let primary_key: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT column_name
+            FROM information_schema.key_column_usage
+            WHERE constraint_name = 'PRIMARY'
+                AND table_schema = COALESCE(?, DATABASE()) AND table_name = ?
+            ORDER BY ordinal_position
+        "#,
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_all(self)
+        .await?;
+
+        let values = columns
+            .iter()
+            .map(|(column, data_type)| {
+                let column = quote_identifier(column, QUOTE)?;
+                Ok(match data_type.as_str() {
+                    "datetime" | "timestamp" => {
+                        format!("DATE_FORMAT({}, '%Y-%m-%d %H:%i:%s')", column)
+                    }
+                    "date" => format!("DATE_FORMAT({}, '%Y-%m-%d 00:00:00')", column),
+                    // Bytes are read as hex, since they are not always valid UTF-8.
+                    // HEX of a bit value has an odd number of digits when its top nibble is 0.
+                    "bit" => format!(
+                        "LPAD(HEX({0}), 2 * CEIL(CHAR_LENGTH(HEX({0})) / 2), '0')",
+                        column
+                    ),
+                    data_type if is_binary(data_type) => format!("HEX({})", column),
+                    _ => format!("CAST({} AS CHAR)", column),
+                })
+            })
+            .collect::<Result<Vec<String>, Error>>()?;
+        let mut sql = format!(
+            "SELECT {} FROM {}",
+            values.join(", "),
+            quote_table(table, QUOTE)?
+        );
+        if !primary_key.is_empty() {
+            let primary_key = primary_key
+                .iter()
+                .map(|x| quote_identifier(&x.0, QUOTE))
+                .collect::<Result<Vec<String>, Error>>()?;
+            sql = format!("{} ORDER BY {}", sql, primary_key.join(", "));
+        }
+
+        let mut records = vec![];
+        let mut cursor = sqlx::query(sql.as_str()).fetch(self);
+        while let Some(row) = cursor.next().await? {
+            let mut record = Hash::new();
+            for (index, (column, data_type)) in columns.iter().enumerate() {
+                let value: Option<String> = row.try_get(index)?;
+                record.insert(
+                    Yaml::String(column.clone()),
+                    value_to_yaml(data_type, value),
+                );
+            }
+            records.push(Yaml::Hash(record));
+        }
+        Ok(records)
+    }
+}
+
+/// Convert the text form of a value to YAML by the type of its column.
+/// MySQL converts strings to the type of the column, so other types are left as strings.
+fn value_to_yaml(data_type: &str, value: Option<String>) -> Yaml {
+    let value = match value {
+        Some(value) => value,
+        None => return Yaml::Null,
+    };
+    match data_type {
+        "tinyint" | "smallint" | "mediumint" | "int" | "bigint" => match value.parse::<i64>() {
+            Ok(value) => Yaml::Integer(value),
+            // An unsigned integer larger than `i64` is read back from a real.
+            Err(_) => Yaml::Real(value),
+        },
+        "decimal" | "float" | "double" => Yaml::Real(value),
+        data_type if is_binary(data_type) => Yaml::String(format!("RAW=X'{}'", value)),
+        _ => Yaml::String(value),
+    }
+}
+
+/// Check if a column of `data_type` stores bytes.
+fn is_binary(data_type: &str) -> bool {
+    matches!(
+        data_type,
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" | "bit"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mysql::dumper::value_to_yaml;
+    use yaml_rust::Yaml;
+
+    #[test]
+    fn test_value_to_yaml() {
+        assert_eq!(value_to_yaml("int", None), Yaml::Null);
+        assert_eq!(
+            value_to_yaml("tinyint", Some("1".to_string())),
+            Yaml::Integer(1)
+        );
+        assert_eq!(
+            value_to_yaml("bigint", Some("18446744073709551615".to_string())),
+            Yaml::Real("18446744073709551615".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("decimal", Some("19.99".to_string())),
+            Yaml::Real("19.99".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("blob", Some("CAFE00FF".to_string())),
+            Yaml::String("RAW=X'CAFE00FF'".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("bit", Some("05".to_string())),
+            Yaml::String("RAW=X'05'".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("json", Some("{}".to_string())),
+            Yaml::String("{}".to_string())
+        );
+    }
+}
@@ -1,26 +1,252 @@
+use crate::cleanup::Cleanup;
+use crate::column_type::{Column, ColumnType};
+use crate::dumper::split_table;
+use crate::error::Error;
 use crate::fixture_file::{FixtureFile, SqlParam};
-use crate::helper::Database as DB;
+use crate::helper::{
+    cleanup_queries, columns_by_table, quote_identifier, quote_table, Database as DB,
+    CHECKSUM_TABLE,
+};
 use async_trait::async_trait;
 use chrono::{Offset, TimeZone};
 use sqlx::mysql::MySqlQueryAs;
-use sqlx::{
-    arguments::Arguments, mysql::MySqlArguments, Error, MySql as M, MySqlConnection, MySqlPool,
-    Query,
-};
+use sqlx::types::BigDecimal;
+use sqlx::{arguments::Arguments, mysql::MySqlArguments, MySql as M, MySqlConnection, MySqlPool};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// The character to quote identifiers.
+const QUOTE: char = '`';
 
 /// **MySQL** helper.
+#[derive(Default)]
 pub struct MySql {
     pub table_names: Vec<String>,
+    /// Primary key columns of each table, by table name and by name qualified with the database.
+    pub primary_keys: HashMap<String, Vec<String>>,
+    /// AUTO_INCREMENT columns of each table, keyed like `primary_keys`.
+    pub auto_increment_columns: HashMap<String, Vec<String>>,
+    /// Columns of each table, keyed like `primary_keys`.
+    pub columns: Option<HashMap<String, Vec<Column>>>,
 }
 
-impl Default for MySql {
-    fn default() -> Self {
-        MySql {
-            table_names: vec![],
+impl MySql {
+    /// Create the bookkeeping table for checksums if it does not exist, and get its quoted name.
+    async fn checksum_table(pool: &MySqlPool) -> Result<String, Error> {
+        let table = quote_identifier(CHECKSUM_TABLE, QUOTE)?;
+        sqlx::query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (checksum VARCHAR(64) NOT NULL)",
+                table
+            )
+            .as_str(),
+        )
+        .execute(pool)
+        .await?;
+        Ok(table)
+    }
+
+    /// Get primary key columns of all tables.
+    async fn primary_keys(
+        pool: &MySqlPool,
+        database: &str,
+    ) -> Result<HashMap<String, Vec<String>>, Error> {
+        let columns: Vec<(String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT table_schema, table_name, column_name
+            FROM information_schema.key_column_usage
+            WHERE constraint_name = 'PRIMARY'
+            ORDER BY table_schema, table_name, ordinal_position
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(columns_by_table(columns, database))
+    }
+
+    /// Get AUTO_INCREMENT columns of all tables.
+    async fn auto_increment_columns(
+        pool: &MySqlPool,
+        database: &str,
+    ) -> Result<HashMap<String, Vec<String>>, Error> {
+        let columns: Vec<(String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT table_schema, table_name, column_name
+            FROM information_schema.columns
+            WHERE extra LIKE '%auto_increment%'
+            ORDER BY table_schema, table_name
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(columns_by_table(columns, database))
+    }
+
+    /// Get columns of all tables.
+    async fn columns(
+        pool: &MySqlPool,
+        database: &str,
+    ) -> Result<HashMap<String, Vec<Column>>, Error> {
+        type Row = (
+            String,
+            String,
+            String,
+            String,
+            String,
+            i64,
+            i64,
+            Option<i64>,
+        );
+        let columns: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT table_schema, table_name, column_name, data_type, column_type,
+                -- NULL in an AUTO_INCREMENT column is replaced with the next value.
+                is_nullable = 'YES' OR extra LIKE '%auto_increment%',
+                column_default IS NOT NULL OR extra LIKE '%auto_increment%' OR extra LIKE '%GENERATED%',
+                CAST(character_maximum_length AS SIGNED)
+            FROM information_schema.columns
+            WHERE table_schema NOT IN ('mysql', 'information_schema', 'performance_schema', 'sys')
+            ORDER BY table_schema, table_name, ordinal_position
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        let columns = columns
+            .into_iter()
+            .map(
+                |(schema, table, name, data_type, full_type, nullable, has_default, max_length)| {
+                    let column = Column {
+                        name,
+                        column_type: column_type(&data_type, &full_type),
+                        nullable: nullable != 0,
+                        has_default: has_default != 0,
+                        // Only lengths of CHAR and VARCHAR columns are checked, not of TEXT ones.
+                        max_length: max_length
+                            .filter(|_| data_type == "char" || data_type == "varchar")
+                            .map(|x| x as usize),
+                    };
+                    (schema, table, column)
+                },
+            )
+            .collect();
+        Ok(columns_by_table(columns, database))
+    }
+
+    /// Move AUTO_INCREMENT counters of `tables` past the largest value in the column.
+    /// ALTER TABLE commits implicitly, so this runs after the fixtures are committed.
+    async fn reset_auto_increment(&self, pool: &MySqlPool, tables: &[String]) -> Result<(), Error> {
+        for table in tables {
+            let column = match self.auto_increment_columns.get(table) {
+                Some(columns) if !columns.is_empty() => &columns[0],
+                _ => continue,
+            };
+            let quoted_table = quote_table(table, QUOTE)?;
+            let sql = format!(
+                "SELECT CAST(COALESCE(MAX({}), 0) + 1 AS CHAR) FROM {}",
+                quote_identifier(column, QUOTE)?,
+                quoted_table
+            );
+            let result: Result<(), sqlx::Error> = async {
+                let next: (String,) = sqlx::query_as(sql.as_str()).fetch_one(pool).await?;
+                // ALTER TABLE takes no bind parameters, so only a number is put in the query.
+                let next = match next.0.parse::<u64>() {
+                    Ok(next) => next,
+                    Err(_) => return Ok(()),
+                };
+                sqlx::query(
+                    format!("ALTER TABLE {} AUTO_INCREMENT = {}", quoted_table, next).as_str(),
+                )
+                .execute(pool)
+                .await?;
+                Ok(())
+            }
+            .await;
+            if let Err(source) = result {
+                return Err(Error::Query {
+                    table: table.clone(),
+                    record_index: None,
+                    source,
+                });
+            }
         }
+        Ok(())
     }
 }
 
+/// Get the type of a column from its `data_type` and `column_type` in information_schema.
+/// `column_type` tells whether an integer is unsigned and which values an enum has.
+fn column_type(data_type: &str, column_type: &str) -> ColumnType {
+    let integer = |bits| match column_type.contains("unsigned") {
+        true => ColumnType::unsigned(bits),
+        false => ColumnType::signed(bits),
+    };
+    match data_type {
+        "tinyint" => integer(8),
+        "smallint" => integer(16),
+        "mediumint" => integer(24),
+        "int" => integer(32),
+        "bigint" => integer(64),
+        "decimal" => ColumnType::Decimal,
+        "float" | "double" => ColumnType::Float,
+        "date" => ColumnType::Date,
+        "time" => ColumnType::Time,
+        "datetime" | "timestamp" => ColumnType::Datetime,
+        "json" => ColumnType::Json,
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => {
+            ColumnType::Binary
+        }
+        "enum" => ColumnType::Enum(enum_labels(column_type)),
+        "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext" => ColumnType::Text,
+        _ => ColumnType::Other,
+    }
+}
+
+/// Get the values of an enum from its `column_type`, like `enum('low','it''s')`.
+fn enum_labels(column_type: &str) -> Vec<String> {
+    let mut labels = vec![];
+    let mut label: Option<String> = None;
+    let mut chars = column_type.chars().peekable();
+    while let Some(c) = chars.next() {
+        match label.as_mut() {
+            None if c == '\'' => label = Some(String::new()),
+            None => (),
+            // A quote in a value is doubled.
+            Some(value) if c == '\'' && chars.peek() == Some(&'\'') => {
+                chars.next();
+                value.push(c);
+            }
+            Some(_) if c == '\'' => labels.extend(label.take()),
+            Some(value) => value.push(c),
+        }
+    }
+    labels
+}
+
+/// Bind parameters for a MySQL query.
+fn arguments<'a, Tz>(params: impl IntoIterator<Item = &'a SqlParam<Tz>>) -> MySqlArguments
+where
+    Tz: TimeZone + Send + Sync + 'a,
+{
+    let mut args = MySqlArguments::default();
+    for param in params {
+        match param {
+            SqlParam::String(param) => args.add(param),
+            SqlParam::Integer(param) => args.add(param),
+            SqlParam::UnsignedInteger(param) => args.add(param),
+            SqlParam::Datetime(param) => args.add(param.naive_local()),
+            SqlParam::Float(param) => args.add(param),
+            SqlParam::Decimal(param) => args.add(BigDecimal::from_str(param).unwrap_or_default()),
+            SqlParam::Boolean(param) => args.add(param),
+            SqlParam::Date(param) => args.add(param),
+            SqlParam::Time(param) => args.add(param),
+            SqlParam::Binary(param) => args.add(param),
+            SqlParam::Json(param) | SqlParam::Enum(param) => args.add(param),
+            SqlParam::Null => args.add(None::<String>),
+        }
+    }
+    args
+}
+
 #[async_trait]
 impl<O, Tz> DB<M, MySqlConnection, O, Tz> for MySql
 where
@@ -28,101 +254,288 @@ where
     Tz: TimeZone<Offset = O> + Send + Sync + 'static,
 {
     /// Initialize MySQL struct.
-    async fn init(&mut self, _pool: &MySqlPool) -> anyhow::Result<()> {
+    async fn init(&mut self, pool: &MySqlPool) -> Result<(), Error> {
+        self.table_names = DB::<M, MySqlConnection, O, Tz>::table_names(self, pool).await?;
+        let database = DB::<M, MySqlConnection, O, Tz>::database_name(self, pool).await?;
+        self.primary_keys = Self::primary_keys(pool, &database).await?;
+        self.auto_increment_columns = Self::auto_increment_columns(pool, &database).await?;
+        self.columns = Some(Self::columns(pool, &database).await?);
         Ok(())
     }
 
     /// Get database name.
-    async fn database_name(&self, pool: &MySqlPool) -> anyhow::Result<String> {
+    async fn database_name(&self, pool: &MySqlPool) -> Result<String, Error> {
         let rec: (String,) = sqlx::query_as("SELECT DATABASE()").fetch_one(pool).await?;
         Ok(rec.0)
     }
 
-    // TODO: complete this function
-    // async fn table_names(&self, pool: &MySqlPool) -> anyhow::Result<Vec<String>> {
-    //     let tables = sqlx::query_as(
-    //         r#"
-    //         SELECT table_name
-    //         FROM information_schema.tables
-    //         WHERE table_schema = ? AND table_type = 'BASE TABLE';
-    //     "#,
-    //     )
-    //     .bind("test")
-    //     .fetch_all(pool)
-    //     .await?;
-
-    //     let mut names = vec![];
-    //     for table in tables {
-    //         names.push(table.table_name)
-    //     }
-    //     Ok(names)
-    // }
+    /// MySQL does not tell which address it was reached at, and the pool does not keep its URL.
+    // `bind_address` and `hostname` describe the server, not the connection:
+    // `*` or a list of addresses says nothing about the host, and a host name is not an address.
+    async fn server_address(&self, _pool: &MySqlPool) -> Result<Option<String>, Error> {
+        Err(Error::Unsupported {
+            option: "require_local_host".to_string(),
+            database: "MySQL".to_string(),
+        })
+    }
+
+    /// Get names of all base tables in the current database.
+    async fn table_names(&self, pool: &MySqlPool) -> Result<Vec<String>, Error> {
+        let tables: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT table_name
+            FROM information_schema.tables
+            WHERE table_schema = DATABASE() AND table_type = 'BASE TABLE'
+            ORDER BY table_name
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(tables
+            .into_iter()
+            .map(|x| x.0)
+            .filter(|x| x != CHECKSUM_TABLE)
+            .collect())
+    }
+
+    /// Get checksums of `tables` by CHECKSUM TABLE, with their AUTO_INCREMENT counters,
+    /// which CHECKSUM TABLE does not cover.
+    async fn fingerprint(&self, pool: &MySqlPool, tables: &[String]) -> Result<String, Error> {
+        if tables.is_empty() {
+            return Ok(String::new());
+        }
+        let mut conn = pool.acquire().await?;
+        let quoted_tables = tables
+            .iter()
+            .map(|x| quote_table(x, QUOTE))
+            .collect::<Result<Vec<String>, Error>>()?;
+        let checksums: Vec<(String, Option<i64>)> =
+            sqlx::query_as(format!("CHECKSUM TABLE {}", quoted_tables.join(", ")).as_str())
+                .fetch_all(&mut conn)
+                .await?;
+        let mut fingerprint: Vec<String> = checksums
+            .into_iter()
+            .map(|(table, checksum)| format!("{}={}", table, checksum.unwrap_or_default()))
+            .collect();
+
+        // MySQL 8.0 caches AUTO_INCREMENT in information_schema for a day by default.
+        // Older servers and MariaDB do not cache it and do not have the variable.
+        let _ = sqlx::query("SET SESSION information_schema_stats_expiry = 0")
+            .execute(&mut conn)
+            .await;
+        for table in tables {
+            let (schema, name) = split_table(table);
+            let counter: Option<(Option<String>,)> = sqlx::query_as(
+                r#"
+                SELECT CAST(auto_increment AS CHAR)
+                FROM information_schema.tables
+                WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?
+            "#,
+            )
+            .bind(schema)
+            .bind(name)
+            .fetch_optional(&mut conn)
+            .await?;
+            if let Some(counter) = counter.and_then(|x| x.0) {
+                fingerprint.push(format!("{}.auto_increment={}", table, counter));
+            }
+        }
+        Ok(fingerprint.join(";"))
+    }
+
+    /// Get the checksum saved by the last load.
+    async fn load_checksum(&self, pool: &MySqlPool) -> Result<Option<String>, Error> {
+        let table = Self::checksum_table(pool).await?;
+        let checksum: Option<(String,)> =
+            sqlx::query_as(format!("SELECT checksum FROM {}", table).as_str())
+                .fetch_optional(pool)
+                .await?;
+        Ok(checksum.map(|x| x.0))
+    }
+
+    /// Save the checksum of a load in the bookkeeping table.
+    async fn save_checksum(&self, pool: &MySqlPool, checksum: &str) -> Result<(), Error> {
+        let table = Self::checksum_table(pool).await?;
+        let mut tx = pool.begin().await?;
+        sqlx::query(format!("DELETE FROM {}", table).as_str())
+            .execute(&mut tx)
+            .await?;
+        sqlx::query(format!("INSERT INTO {} (checksum) VALUES (?)", table).as_str())
+            .bind(checksum)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn schema(&self) -> Option<&HashMap<String, Vec<Column>>> {
+        self.columns.as_ref()
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> Result<String, Error> {
+        quote_identifier(identifier, QUOTE)
+    }
+
+    // Prepared statements can have at most 65535 placeholders.
+    fn max_bind_params(&self) -> usize {
+        u16::MAX as usize
+    }
 
     /// Execute SQL queries in a transaction for MySQL.
     async fn with_transaction(
         &self,
         pool: &MySqlPool,
         fixture_files: &[FixtureFile<Tz>],
-    ) -> anyhow::Result<()> {
+        cleanup: Cleanup,
+        clean_all_tables: bool,
+    ) -> Result<(), Error> {
         let mut tx = pool.begin().await?;
+        match self
+            .execute(&mut tx, fixture_files, cleanup, clean_all_tables)
+            .await
+        {
+            Ok(_) => {
+                tx.commit().await?;
+            }
+            Err(err) => {
+                // The error of the query is returned even if the rollback fails.
+                let _ = tx.rollback().await;
+                return Err(err);
+            }
+        };
+        // ALTER TABLE commits implicitly, so counters are reset after the fixtures are committed.
+        let tables: Vec<String> = fixture_files
+            .iter()
+            .flat_map(|x| x.tables.clone())
+            .collect();
+        self.reset_auto_increment(pool, &tables).await?;
+        Ok(())
+    }
 
+    async fn execute(
+        &self,
+        conn: &mut MySqlConnection,
+        fixture_files: &[FixtureFile<Tz>],
+        cleanup: Cleanup,
+        clean_all_tables: bool,
+    ) -> Result<(), Error> {
         let mut queries = vec![];
-        let delete_queries: Vec<String> = fixture_files.iter().map(|x| (x.delete())).collect();
-        let mut delete_queries: Vec<Query<'_, M>> =
-            delete_queries.iter().map(|x| sqlx::query(x)).collect();
-        queries.append(&mut delete_queries);
+        let cleanup_queries = cleanup_queries(
+            self,
+            fixture_files,
+            &self.table_names,
+            &self.primary_keys,
+            cleanup,
+            clean_all_tables,
+            |x| quote_table(x, QUOTE),
+            |tables| {
+                tables
+                    .iter()
+                    .map(|(table, quoted)| (table.clone(), format!("TRUNCATE TABLE {}", quoted)))
+                    .collect()
+            },
+        )?;
+        for (table, sql, params) in &cleanup_queries {
+            queries.push((
+                table.clone(),
+                None,
+                sqlx::query(sql.as_str()).bind_all(arguments(params.iter().copied())),
+            ));
+        }
 
         for fixtures_file in fixture_files {
             for sql in &fixtures_file.insert_sqls {
-                let mut args = MySqlArguments::default();
-                for param in &sql.params {
-                    match param {
-                        SqlParam::String(param) => args.add(param),
-                        SqlParam::Integer(param) => args.add(param),
-                        SqlParam::Datetime(param) => args.add(param.naive_local()),
-                        SqlParam::Float(param) => args.add(param),
-                        SqlParam::Boolean(param) => args.add(param),
-                    }
-                }
-                queries.push(sqlx::query(sql.sql.as_str()).bind_all(args))
+                queries.push((
+                    sql.table.clone(),
+                    Some(sql.record_index),
+                    sqlx::query(sql.sql.as_str()).bind_all(arguments(&sql.params)),
+                ));
             }
         }
 
-        let result: Result<u64, Error> = async {
-            sqlx::query("SET FOREIGN_KEY_CHECKS = 0")
-                .execute(&mut tx)
-                .await?;
-
-            for query in queries {
-                let result = query.execute(&mut tx).await;
-                if result.is_err() {
-                    return result;
-                }
+        sqlx::query("SET FOREIGN_KEY_CHECKS = 0")
+            .execute(&mut *conn)
+            .await?;
+        let mut result = Ok(());
+        for (table, record_index, query) in queries {
+            if let Err(source) = query.execute(&mut *conn).await {
+                result = Err(Error::Query {
+                    table,
+                    record_index,
+                    source,
+                });
+                break;
             }
-
-            sqlx::query("SET FOREIGN_KEY_CHECKS = 1")
-                .execute(&mut tx)
-                .await?;
-            Ok(1)
         }
-        .await;
+        // The setting belongs to the session, so it is restored even if a query fails.
+        sqlx::query("SET FOREIGN_KEY_CHECKS = 1")
+            .execute(&mut *conn)
+            .await?;
+        result
+    }
+}
 
-        match result {
-            Ok(_) => {
-                tx.commit().await?;
-            }
-            Err(err) => {
-                tx.rollback().await?;
-                return Err(anyhow::anyhow!("testfixtures: {}", err));
+#[cfg(test)]
+mod tests {
+    use crate::column_type::ColumnType;
+    use crate::error::Error;
+    use crate::helper::Database as DB;
+    use crate::mysql::helper::{column_type, enum_labels, MySql};
+    use chrono::Utc;
+    use sqlx::{MySql as M, MySqlConnection, MySqlPool};
+
+    #[test]
+    fn test_column_type() {
+        assert_eq!(column_type("tinyint", "tinyint(1)"), ColumnType::signed(8));
+        assert_eq!(
+            column_type("int", "int(10) unsigned"),
+            ColumnType::unsigned(32)
+        );
+        assert_eq!(column_type("varchar", "varchar(255)"), ColumnType::Text);
+        assert_eq!(
+            column_type("enum", "enum('low','high')"),
+            ColumnType::Enum(vec!["low".to_string(), "high".to_string()])
+        );
+        assert_eq!(column_type("set", "set('a','b')"), ColumnType::Other);
+    }
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_server_address() -> anyhow::Result<()> {
+        // The pool never connects, so no `bind_address` of `*`, a list of addresses
+        // or a host name can make a remote server look local.
+        for url in [
+            "mysql://root@127.0.0.1:1/test",
+            "mysql://root@10.0.0.5:1/test",
+            "mysql://root@db.example.com:1/test",
+        ] {
+            let pool = MySqlPool::new(url).await?;
+            let result =
+                DB::<M, MySqlConnection, Utc, Utc>::server_address(&MySql::default(), &pool).await;
+            match result {
+                Err(err @ Error::Unsupported { .. }) => assert_eq!(
+                    err.to_string(),
+                    "testfixtures: require_local_host is not supported on MySQL"
+                ),
+                _ => panic!("expected an unsupported error"),
             }
-        };
+        }
         Ok(())
     }
+
+    #[test]
+    fn test_enum_labels() {
+        assert_eq!(
+            enum_labels("enum('it''s','a,b','')"),
+            vec!["it's".to_string(), "a,b".to_string(), "".to_string()]
+        );
+    }
 }
 
 #[cfg(test)]
 #[cfg(feature = "mysql")]
-mod tests {
+mod mysql_tests {
+    use crate::cleanup::Cleanup;
     use crate::fixture_file::FixtureFile;
     use crate::mysql::helper::MySql;
     use crate::mysql::loader::MySqlLoader;
@@ -165,14 +578,15 @@ mod tests {
                 .unwrap()
                 .to_string(),
             content: File::open(fixture_file_path).unwrap(),
+            tables: vec![],
             insert_sqls: vec![],
         };
         loader.fixture_files = vec![fixture_file];
-        loader.build_insert_sqls();
+        loader.build_insert_sqls()?;
         let result = loader
             .helper
             .unwrap()
-            .with_transaction(&pool, &loader.fixture_files)
+            .with_transaction(&pool, &loader.fixture_files, Cleanup::Delete, false)
             .await;
 
         if let Err(err) = result {
@@ -190,9 +604,15 @@ mod tests {
         let created_at: NaiveDateTime = row.get("created_at");
         assert_eq!(id, 1);
         assert_eq!(description, "fizz");
-        assert_eq!(done, false);
+        assert!(!done);
         assert_eq!(progress, 10.5);
-        assert_eq!(created_at, NaiveDate::from_ymd(2020, 1, 1).and_hms(1, 1, 1));
+        assert_eq!(
+            created_at,
+            NaiveDate::from_ymd_opt(2020, 1, 1)
+                .unwrap()
+                .and_hms_opt(1, 1, 1)
+                .unwrap()
+        );
         Ok(())
     }
 }
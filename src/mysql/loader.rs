@@ -1,3 +1,4 @@
+use crate::error::Error;
 use crate::loader::Loader;
 use crate::mysql::helper;
 use chrono::{Offset, TimeZone};
@@ -29,26 +30,20 @@ where
     ///     }
     /// }
     /// ```
-    pub async fn new<F>(options: F) -> anyhow::Result<MySqlLoader<O, Tz>>
+    pub async fn new<F>(options: F) -> Result<MySqlLoader<O, Tz>, Error>
     where
         F: FnOnce(&mut MySqlLoader<O, Tz>),
     {
         let mut loader = Self::default();
         options(&mut loader);
         if loader.location.is_none() {
-            return Err(anyhow::anyhow!("testfixtures: you need a location"));
+            return Err(Error::MissingLocation);
         }
         if loader.pool.is_none() {
-            return Err(anyhow::anyhow!("testfixtures: you need a pool"));
+            return Err(Error::MissingPool);
         }
         loader.helper = Some(Box::new(helper::MySql::default()));
-        loader.build_insert_sqls();
-        loader
-            .helper
-            .as_mut()
-            .unwrap()
-            .init(loader.pool.as_ref().unwrap())
-            .await?;
+        loader.prepare().await?;
         Ok(loader)
     }
 }
@@ -57,7 +52,38 @@ where
 mod tests {
     use crate::mysql::loader::MySqlLoader;
     use chrono::Utc;
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn it_returns_option_error() -> anyhow::Result<()> {
+        let result = MySqlLoader::<Utc, Utc>::new(|cfg| {
+            cfg.skip_test_database_check();
+        })
+        .await;
+        assert!(result.is_err());
+        if let Err(err) = result {
+            assert_eq!(err.to_string(), "testfixtures: you need a location");
+        }
+
+        let result = MySqlLoader::<Utc, Utc>::new(|cfg| {
+            cfg.location(Utc);
+        })
+        .await;
+        assert!(result.is_err());
+        if let Err(err) = result {
+            assert_eq!(err.to_string(), "testfixtures: you need a pool");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "mysql")]
+mod mysql_tests {
+    use crate::mysql::loader::MySqlLoader;
+    use chrono::Utc;
     use sqlx::MySqlPool;
+    use std::env;
     use std::fs::File;
     use std::io::Write;
     use tempfile::tempdir;
@@ -74,11 +100,10 @@ mod tests {
             r#"
         - id: 1
           description: fizz
-          created_at: 2020/01/01 01:01:01
-          updated_at: RAW=NOW()"#
+          created_at: 2020/01/01 01:01:01"#
         )?;
 
-        let pool = MySqlPool::new("fizz").await?;
+        let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
         let loader = MySqlLoader::new(|cfg| {
             cfg.location(Utc);
             cfg.database(pool);
@@ -1,2 +1,4 @@
+pub mod dumper;
 pub mod helper;
 pub mod loader;
+pub mod test_database;
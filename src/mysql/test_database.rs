@@ -0,0 +1,122 @@
+use crate::error::Error;
+use crate::helper::quote_identifier;
+use crate::mysql::loader::MySqlLoader;
+use crate::test_database::{database_name, Provision, TestDatabase};
+use async_trait::async_trait;
+use chrono::{Offset, TimeZone};
+use sqlx::mysql::MySqlQueryAs;
+use sqlx::{Connect, Connection, MySqlConnection};
+use std::fmt::Display;
+
+/// The character to quote identifiers.
+const QUOTE: char = '`';
+
+/// An alias for [TestDatabase](crate::TestDatabase), specialized for **MySQL**.
+pub type MySqlTestDatabase = TestDatabase<MySqlConnection>;
+
+#[async_trait]
+impl Provision for MySqlConnection {
+    async fn create_database(template_url: &str, name: &str) -> Result<(), Error> {
+        let mut conn = MySqlConnection::connect(template_url).await?;
+        let tables: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT table_name
+            FROM information_schema.tables
+            WHERE table_schema = DATABASE() AND table_type = 'BASE TABLE'
+        "#,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        let name = quote_identifier(name, QUOTE)?;
+        let template = quote_identifier(database_name(template_url), QUOTE)?;
+        sqlx::query(&format!("CREATE DATABASE {}", name))
+            .execute(&mut conn)
+            .await?;
+        if let Err(err) = copy_tables(&mut conn, &name, &template, tables).await {
+            // The database is not left behind if a table can not be copied.
+            let _ = sqlx::query(&format!("DROP DATABASE IF EXISTS {}", name))
+                .execute(&mut conn)
+                .await;
+            return Err(err);
+        }
+        conn.close().await?;
+        Ok(())
+    }
+
+    async fn drop_database(template_url: &str, name: &str) -> Result<(), Error> {
+        let mut conn = MySqlConnection::connect(template_url).await?;
+        sqlx::query(&format!(
+            "DROP DATABASE IF EXISTS {}",
+            quote_identifier(name, QUOTE)?
+        ))
+        .execute(&mut conn)
+        .await?;
+        conn.close().await?;
+        Ok(())
+    }
+}
+
+/// Create `tables` of the database `template` in the database `name`. Both names are quoted.
+// CREATE TABLE ... LIKE copies columns and indexes, but not foreign keys.
+async fn copy_tables(
+    conn: &mut MySqlConnection,
+    name: &str,
+    template: &str,
+    tables: Vec<(String,)>,
+) -> Result<(), Error> {
+    for (table,) in tables {
+        let table = quote_identifier(&table, QUOTE)?;
+        sqlx::query(&format!(
+            "CREATE TABLE {}.{} LIKE {}.{}",
+            name, table, template, table
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+impl MySqlTestDatabase {
+    /// Create a database with the tables of the database at `template_url`, and load fixtures into it.
+    ///
+    /// `options` sets options of the [Loader](crate::loader::Loader) except the database,
+    /// and the test database check is skipped since the database is created here.
+    ///
+    /// # Example
+    /// ```rust
+    /// #[cfg(test)]
+    /// mod tests {
+    ///     use chrono::Utc;
+    ///     use testfixtures::MySqlTestDatabase;
+    ///     #[async_std::test]
+    ///     async fn test_something() -> anyhow::Result<()> {
+    ///         let database = MySqlTestDatabase::new("mysql://root@127.0.0.1:3314/test", |cfg| {
+    ///             cfg.location(Utc);
+    ///             cfg.paths(vec!["fixtures/todos.yml"]);
+    ///         })
+    ///         .await?;
+    ///         let pool = database.pool();
+    ///         // run your tests
+    ///         database.close().await?;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    pub async fn new<O, Tz, F>(template_url: &str, options: F) -> Result<MySqlTestDatabase, Error>
+    where
+        O: Offset + Display + Send + Sync + 'static,
+        Tz: TimeZone<Offset = O> + Send + Sync + 'static,
+        F: FnOnce(&mut MySqlLoader<O, Tz>),
+    {
+        let database = Self::create(template_url).await?;
+        let pool = database.pool().clone();
+        let loader = MySqlLoader::new(|cfg| {
+            options(cfg);
+            cfg.database(pool);
+            cfg.skip_test_database_check();
+        })
+        .await?;
+        loader.load().await?;
+        Ok(database)
+    }
+}
@@ -0,0 +1,154 @@
+use crate::dumper::{split_table, Dump};
+use crate::error::Error;
+use crate::helper::{quote_identifier, quote_table};
+use async_trait::async_trait;
+use sqlx::cursor::Cursor;
+use sqlx::postgres::PgQueryAs;
+use sqlx::{PgPool, Row};
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+
+/// The character to quote identifiers.
+const QUOTE: char = '"';
+
+#[async_trait]
+impl Dump for PgPool {
+    async fn records(&self, table: &str) -> Result<Vec<Yaml>, Error> {
+        let (schema, name) = split_table(table);
+        let columns: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT column_name::text, data_type::text
+            FROM information_schema.columns
+            WHERE table_schema = COALESCE($1, current_schema()) AND table_name = $2
+            ORDER BY ordinal_position
+        "#,
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_all(self)
+        .await?;
+        let primary_key: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT kcu.column_name::text
+            FROM information_schema.table_constraints tc
+            JOIN information_schema.key_column_usage kcu
+                ON kcu.constraint_schema = tc.constraint_schema
+                AND kcu.constraint_name = tc.constraint_name
+            WHERE tc.constraint_type = 'PRIMARY KEY'
+                AND tc.table_schema = COALESCE($1, current_schema()) AND tc.table_name = $2
+            ORDER BY kcu.ordinal_position
+        "#,
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_all(self)
+        .await?;
+
+        let values = columns
+            .iter()
+            .map(|(column, data_type)| {
+                let column = quote_identifier(column, QUOTE)?;
+                Ok(match data_type.as_str() {
+                    "timestamp without time zone" | "timestamp with time zone" => {
+                        format!("to_char({}, 'YYYY-MM-DD HH24:MI:SS')", column)
+                    }
+                    "date" => format!("to_char({}, 'YYYY-MM-DD \"00:00:00\"')", column),
+                    _ => format!("{}::text", column),
+                })
+            })
+            .collect::<Result<Vec<String>, Error>>()?;
+        let mut sql = format!(
+            "SELECT {} FROM {}",
+            values.join(", "),
+            quote_table(table, QUOTE)?
+        );
+        if !primary_key.is_empty() {
+            let primary_key = primary_key
+                .iter()
+                .map(|x| quote_identifier(&x.0, QUOTE))
+                .collect::<Result<Vec<String>, Error>>()?;
+            sql = format!("{} ORDER BY {}", sql, primary_key.join(", "));
+        }
+
+        let mut records = vec![];
+        let mut cursor = sqlx::query(sql.as_str()).fetch(self);
+        while let Some(row) = cursor.next().await? {
+            let mut record = Hash::new();
+            for (index, (column, data_type)) in columns.iter().enumerate() {
+                let value: Option<String> = row.try_get(index)?;
+                record.insert(
+                    Yaml::String(column.clone()),
+                    value_to_yaml(data_type, value),
+                );
+            }
+            records.push(Yaml::Hash(record));
+        }
+        Ok(records)
+    }
+}
+
+/// Convert the text form of a value to YAML by the type of its column.
+fn value_to_yaml(data_type: &str, value: Option<String>) -> Yaml {
+    let value = match value {
+        Some(value) => value,
+        None => return Yaml::Null,
+    };
+    match data_type {
+        "smallint" | "integer" | "bigint" => match value.parse::<i64>() {
+            Ok(value) => Yaml::Integer(value),
+            Err(_) => Yaml::Real(value),
+        },
+        "numeric" => Yaml::Real(value),
+        "real" | "double precision" => match value.as_str() {
+            "Infinity" => Yaml::Real(".inf".to_string()),
+            "-Infinity" => Yaml::Real("-.inf".to_string()),
+            "NaN" => Yaml::Real(".nan".to_string()),
+            _ => Yaml::Real(value),
+        },
+        "boolean" => Yaml::Boolean(value == "true"),
+        "text"
+        | "character varying"
+        | "character"
+        | "timestamp without time zone"
+        | "timestamp with time zone"
+        | "date" => Yaml::String(value),
+        // Strings are bound as text, which PostgreSQL does not convert to other types.
+        // An untyped literal is converted to the type of the column.
+        _ => Yaml::String(format!("RAW='{}'", value.replace('\'', "''"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::postgresql::dumper::value_to_yaml;
+    use yaml_rust::Yaml;
+
+    #[test]
+    fn test_value_to_yaml() {
+        assert_eq!(value_to_yaml("integer", None), Yaml::Null);
+        assert_eq!(
+            value_to_yaml("bigint", Some("42".to_string())),
+            Yaml::Integer(42)
+        );
+        assert_eq!(
+            value_to_yaml("numeric", Some("19.99".to_string())),
+            Yaml::Real("19.99".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("double precision", Some("-Infinity".to_string())),
+            Yaml::Real("-.inf".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("boolean", Some("true".to_string())),
+            Yaml::Boolean(true)
+        );
+        assert_eq!(
+            value_to_yaml("text", Some("fizz".to_string())),
+            Yaml::String("fizz".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("jsonb", Some(r#"{"name": "it's"}"#.to_string())),
+            Yaml::String(r#"RAW='{"name": "it''s"}'"#.to_string())
+        );
+    }
+}
@@ -1,23 +1,214 @@
+use crate::cleanup::Cleanup;
+use crate::column_type::{Column, ColumnType};
+use crate::error::Error;
 use crate::fixture_file::{FixtureFile, SqlParam};
-use crate::helper::Database as DB;
+use crate::helper::{
+    cleanup_queries, columns_by_table, quote_identifier, quote_table, Database as DB,
+    CHECKSUM_TABLE,
+};
 use async_trait::async_trait;
 use chrono::{Offset, TimeZone};
-use sqlx::postgres::PgQueryAs;
-use sqlx::{
-    arguments::Arguments, postgres::PgArguments, PgConnection, PgPool, Postgres as P, Query,
-};
+use sqlx::encode::{Encode, IsNull};
+use sqlx::postgres::{PgQueryAs, PgRawBuffer, PgTypeInfo};
+use sqlx::types::{BigDecimal, Type};
+use sqlx::{arguments::Arguments, postgres::PgArguments, PgConnection, PgPool, Postgres as P};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// The character to quote identifiers.
+const QUOTE: char = '"';
 
 /// **PostgreSQL** helper.
+#[derive(Default)]
 pub struct PostgreSql {
     pub table_names: Vec<String>,
+    /// Primary key columns of each table, by table name and by name qualified with the schema.
+    pub primary_keys: HashMap<String, Vec<String>>,
+    /// Serial and identity columns of each table, keyed like `primary_keys`.
+    pub serial_columns: HashMap<String, Vec<String>>,
+    /// Columns of each table, keyed like `primary_keys`.
+    pub columns: Option<HashMap<String, Vec<Column>>>,
+}
+
+impl PostgreSql {
+    /// Create the bookkeeping table for checksums if it does not exist, and get its quoted name.
+    async fn checksum_table(pool: &PgPool) -> Result<String, Error> {
+        let table = quote_identifier(CHECKSUM_TABLE, QUOTE)?;
+        sqlx::query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (checksum VARCHAR(64) NOT NULL)",
+                table
+            )
+            .as_str(),
+        )
+        .execute(pool)
+        .await?;
+        Ok(table)
+    }
+
+    /// Get primary key columns of all tables.
+    async fn primary_keys(
+        pool: &PgPool,
+        schema: &str,
+    ) -> Result<HashMap<String, Vec<String>>, Error> {
+        let columns: Vec<(String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT kcu.table_schema::text, kcu.table_name::text, kcu.column_name::text
+            FROM information_schema.table_constraints tc
+            JOIN information_schema.key_column_usage kcu
+                ON kcu.constraint_schema = tc.constraint_schema
+                AND kcu.constraint_name = tc.constraint_name
+            WHERE tc.constraint_type = 'PRIMARY KEY'
+            ORDER BY kcu.table_schema, kcu.table_name, kcu.ordinal_position
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(columns_by_table(columns, schema))
+    }
+
+    /// Get columns of all tables which take their default from a sequence,
+    /// which are serial and identity columns.
+    async fn serial_columns(
+        pool: &PgPool,
+        schema: &str,
+    ) -> Result<HashMap<String, Vec<String>>, Error> {
+        let columns: Vec<(String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT table_schema::text, table_name::text, column_name::text
+            FROM information_schema.columns
+            WHERE column_default LIKE 'nextval(%' OR is_identity = 'YES'
+            ORDER BY table_schema, table_name, ordinal_position
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(columns_by_table(columns, schema))
+    }
+
+    /// Get columns of all tables.
+    async fn columns(pool: &PgPool, schema: &str) -> Result<HashMap<String, Vec<Column>>, Error> {
+        let labels: Vec<(String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT n.nspname::text, t.typname::text, e.enumlabel::text
+            FROM pg_enum e
+            JOIN pg_type t ON t.oid = e.enumtypid
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            ORDER BY n.nspname, t.typname, e.enumsortorder
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        let mut enums: HashMap<(String, String), Vec<String>> = HashMap::new();
+        for (schema, name, label) in labels {
+            enums.entry((schema, name)).or_default().push(label);
+        }
+
+        type Row = (
+            String,
+            String,
+            String,
+            String,
+            String,
+            String,
+            bool,
+            bool,
+            Option<i32>,
+        );
+        let columns: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT table_schema::text, table_name::text, column_name::text,
+                data_type::text, udt_schema::text, udt_name::text,
+                is_nullable = 'YES',
+                column_default IS NOT NULL OR is_identity = 'YES' OR is_generated = 'ALWAYS',
+                character_maximum_length::int
+            FROM information_schema.columns
+            WHERE table_schema NOT IN ('pg_catalog', 'information_schema')
+            ORDER BY table_schema, table_name, ordinal_position
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        let columns = columns
+            .into_iter()
+            .map(
+                |(
+                    table_schema,
+                    table,
+                    name,
+                    data_type,
+                    udt_schema,
+                    udt_name,
+                    nullable,
+                    has_default,
+                    max_length,
+                )| {
+                    let labels = enums.get(&(udt_schema, udt_name)).cloned();
+                    let column = Column {
+                        name,
+                        column_type: column_type(&data_type, labels),
+                        nullable,
+                        has_default,
+                        max_length: max_length.map(|x| x as usize),
+                    };
+                    (table_schema, table, column)
+                },
+            )
+            .collect();
+        Ok(columns_by_table(columns, schema))
+    }
+}
+
+/// Get the type of a column from its `data_type` in information_schema,
+/// and the labels of its enum type if it is one.
+fn column_type(data_type: &str, labels: Option<Vec<String>>) -> ColumnType {
+    match data_type {
+        "smallint" => ColumnType::signed(16),
+        "integer" => ColumnType::signed(32),
+        "bigint" => ColumnType::signed(64),
+        "numeric" => ColumnType::Decimal,
+        "real" | "double precision" => ColumnType::Float,
+        "boolean" => ColumnType::Boolean,
+        "date" => ColumnType::Date,
+        "time without time zone" => ColumnType::Time,
+        "timestamp without time zone" | "timestamp with time zone" => ColumnType::Datetime,
+        "json" | "jsonb" => ColumnType::Json,
+        "bytea" => ColumnType::Binary,
+        "text" | "character varying" | "character" => ColumnType::Text,
+        "USER-DEFINED" => labels.map_or(ColumnType::Other, ColumnType::Enum),
+        _ => ColumnType::Other,
+    }
 }
 
-impl Default for PostgreSql {
-    fn default() -> Self {
-        PostgreSql {
-            table_names: vec![],
+/// Bind parameters for a PostgreSQL query.
+fn arguments<'a, Tz>(params: impl IntoIterator<Item = &'a SqlParam<Tz>>) -> PgArguments
+where
+    Tz: TimeZone + Send + Sync + 'a,
+{
+    let mut args = PgArguments::default();
+    for param in params {
+        match param {
+            SqlParam::String(param) => args.add(param),
+            SqlParam::Integer(param) => args.add(param),
+            // There are no unsigned integer types, so larger values are left to the database to convert.
+            SqlParam::UnsignedInteger(param) => match i64::try_from(*param) {
+                Ok(param) => args.add(param),
+                Err(_) => args.add(param.to_string()),
+            },
+            SqlParam::Datetime(param) => args.add(param.naive_local()),
+            SqlParam::Float(param) => args.add(param),
+            SqlParam::Decimal(param) => args.add(BigDecimal::from_str(param).unwrap_or_default()),
+            SqlParam::Boolean(param) => args.add(param),
+            SqlParam::Date(param) => args.add(param),
+            SqlParam::Time(param) => args.add(param),
+            SqlParam::Binary(param) => args.add(param),
+            SqlParam::Json(param) => args.add(Jsonb(param)),
+            SqlParam::Enum(param) => args.add(Label(param)),
+            SqlParam::Null => args.add(Null),
         }
     }
+    args
 }
 
 #[async_trait]
@@ -26,74 +217,440 @@ where
     Tz: TimeZone<Offset = O> + Send + Sync + 'static,
     O: Offset + Sync + Send + 'static,
 {
-    async fn init(&mut self, _pool: &PgPool) -> anyhow::Result<()> {
+    /// Initialize PostgreSQL struct.
+    async fn init(&mut self, pool: &PgPool) -> Result<(), Error> {
+        self.table_names = DB::<P, PgConnection, O, Tz>::table_names(self, pool).await?;
+        let schema: (Option<String>,) = sqlx::query_as("SELECT current_schema()::text")
+            .fetch_one(pool)
+            .await?;
+        let schema = schema.0.unwrap_or_default();
+        self.primary_keys = Self::primary_keys(pool, &schema).await?;
+        self.serial_columns = Self::serial_columns(pool, &schema).await?;
+        self.columns = Some(Self::columns(pool, &schema).await?);
         Ok(())
     }
 
-    async fn database_name(&self, pool: &PgPool) -> anyhow::Result<String> {
-        let rec: (String,) = sqlx::query_as("SELECT DATABASE()").fetch_one(pool).await?;
+    /// Get database name.
+    async fn database_name(&self, pool: &PgPool) -> Result<String, Error> {
+        let rec: (String,) = sqlx::query_as("SELECT current_database()")
+            .fetch_one(pool)
+            .await?;
+        Ok(rec.0)
+    }
+
+    /// Get the address of the server. A Unix-domain socket is on localhost.
+    async fn server_address(&self, pool: &PgPool) -> Result<Option<String>, Error> {
+        let rec: (Option<String>,) =
+            sqlx::query_as("SELECT COALESCE(host(inet_server_addr()), 'localhost')")
+                .fetch_one(pool)
+                .await?;
         Ok(rec.0)
     }
 
-    // TODO: complete this function
-    // async fn table_names(&self, pool: &PgPool) -> anyhow::Result<Vec<String>> {
-    //     let tables = sqlx::query!(
-    //         r#"
-    //         SELECT table_name
-    //         FROM information_schema.tables
-    //         WHERE table_schema = ? AND table_type = 'BASE TABLE';
-    //     "#,
-    //         "test"
-    //     )
-    //     .fetch_all(pool)
-    //     .await?;
-
-    //     let mut names = vec![];
-    //     for table in tables {
-    //         names.push(table.table_name)
-    //     }
-    //     Ok(names)
-    // }
+    /// Get names of all base tables in the current schema.
+    async fn table_names(&self, pool: &PgPool) -> Result<Vec<String>, Error> {
+        let tables: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT table_name::text
+            FROM information_schema.tables
+            WHERE table_schema = current_schema() AND table_type = 'BASE TABLE'
+            ORDER BY table_name
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(tables
+            .into_iter()
+            .map(|x| x.0)
+            .filter(|x| x != CHECKSUM_TABLE)
+            .collect())
+    }
+
+    /// Get MD5 hashes of the rows in `tables`, with the last values of their sequences.
+    async fn fingerprint(&self, pool: &PgPool, tables: &[String]) -> Result<String, Error> {
+        let mut fingerprint = vec![];
+        for table in tables {
+            let sql = format!(
+                "SELECT md5(COALESCE(string_agg(x::text, ',' ORDER BY x::text), '')) FROM {} x",
+                quote_table(table, QUOTE)?
+            );
+            let hash: (String,) = sqlx::query_as(sql.as_str()).fetch_one(pool).await?;
+            fingerprint.push(format!("{}={}", table, hash.0));
+            // Rows which were inserted and deleted leave the same hash, but move the sequences.
+            for column in self.serial_columns.get(table).into_iter().flatten() {
+                let value: (Option<String>,) = sqlx::query_as(
+                    r#"
+                    SELECT last_value::text
+                    FROM pg_sequences
+                    WHERE format('%I.%I', schemaname, sequencename)::regclass
+                        = pg_get_serial_sequence($1, $2)::regclass
+                "#,
+                )
+                .bind(quote_table(table, QUOTE)?)
+                .bind(column)
+                .fetch_optional(pool)
+                .await?
+                .unwrap_or((None,));
+                fingerprint.push(format!(
+                    "{}.{}={}",
+                    table,
+                    column,
+                    value.0.unwrap_or_default()
+                ));
+            }
+        }
+        Ok(fingerprint.join(";"))
+    }
+
+    /// Get the checksum saved by the last load.
+    async fn load_checksum(&self, pool: &PgPool) -> Result<Option<String>, Error> {
+        let table = Self::checksum_table(pool).await?;
+        let checksum: Option<(String,)> =
+            sqlx::query_as(format!("SELECT checksum FROM {}", table).as_str())
+                .fetch_optional(pool)
+                .await?;
+        Ok(checksum.map(|x| x.0))
+    }
+
+    /// Save the checksum of a load in the bookkeeping table.
+    async fn save_checksum(&self, pool: &PgPool, checksum: &str) -> Result<(), Error> {
+        let table = Self::checksum_table(pool).await?;
+        let mut tx = pool.begin().await?;
+        sqlx::query(format!("DELETE FROM {}", table).as_str())
+            .execute(&mut tx)
+            .await?;
+        sqlx::query(format!("INSERT INTO {} (checksum) VALUES ($1)", table).as_str())
+            .bind(checksum)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn schema(&self) -> Option<&HashMap<String, Vec<Column>>> {
+        self.columns.as_ref()
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> Result<String, Error> {
+        quote_identifier(identifier, QUOTE)
+    }
+
+    fn placeholder(&self, index: usize) -> String {
+        format!("${}", index)
+    }
+
+    // The number of parameters is sent as a 16-bit signed integer.
+    fn max_bind_params(&self) -> usize {
+        i16::MAX as usize
+    }
 
+    /// Execute SQL queries in a transaction for PostgreSQL.
     async fn with_transaction(
         &self,
         pool: &PgPool,
         fixture_files: &[FixtureFile<Tz>],
-    ) -> anyhow::Result<()> {
+        cleanup: Cleanup,
+        clean_all_tables: bool,
+    ) -> Result<(), Error> {
         let mut tx = pool.begin().await?;
-        let result: anyhow::Result<()> = async {
-            let mut queries = vec![];
-            let delete_queries: Vec<String> = fixture_files.iter().map(|x| (x.delete())).collect();
-            let mut delete_queries: Vec<Query<'_, P>> =
-                delete_queries.iter().map(|x| sqlx::query(x)).collect();
-            queries.append(&mut delete_queries);
-
-            for fixtures_file in fixture_files {
-                for sql in &fixtures_file.insert_sqls {
-                    let mut args = PgArguments::default();
-                    for param in &sql.params {
-                        match param {
-                            SqlParam::String(param) => args.add(param),
-                            SqlParam::Integer(param) => args.add(param),
-                            SqlParam::Datetime(param) => args.add(param.naive_local()),
-                            SqlParam::Float(param) => args.add(param),
-                            SqlParam::Boolean(param) => args.add(param),
-                        }
-                    }
-                    queries.push(sqlx::query(sql.sql.as_str()).bind_all(args))
-                }
+        match self
+            .execute(&mut tx, fixture_files, cleanup, clean_all_tables)
+            .await
+        {
+            Ok(_) => {
+                tx.commit().await?;
             }
-            for query in queries {
-                query.execute(&mut tx).await?;
+            Err(err) => {
+                // The error of the query is returned even if the rollback fails.
+                let _ = tx.rollback().await;
+                return Err(err);
             }
-            Ok(())
+        };
+        Ok(())
+    }
+
+    async fn execute(
+        &self,
+        conn: &mut PgConnection,
+        fixture_files: &[FixtureFile<Tz>],
+        cleanup: Cleanup,
+        clean_all_tables: bool,
+    ) -> Result<(), Error> {
+        let mut queries = vec![];
+        // Tables are truncated by one query, so foreign keys between them do not get in the way.
+        let cleanup_queries = cleanup_queries(
+            self,
+            fixture_files,
+            &self.table_names,
+            &self.primary_keys,
+            cleanup,
+            clean_all_tables,
+            |x| quote_table(x, QUOTE),
+            |tables| {
+                let (tables, quoted): (Vec<String>, Vec<String>) = tables.iter().cloned().unzip();
+                vec![(
+                    tables.join(", "),
+                    format!("TRUNCATE TABLE {} RESTART IDENTITY", quoted.join(", ")),
+                )]
+            },
+        )?;
+        for (table, sql, params) in &cleanup_queries {
+            queries.push((
+                table.clone(),
+                None,
+                sqlx::query(sql.as_str()).bind_all(arguments(params.iter().copied())),
+            ));
         }
-        .await;
-        if result.is_ok() {
-            tx.commit().await?;
-        } else {
-            tx.rollback().await?;
+
+        for fixtures_file in fixture_files {
+            for insert_sql in &fixtures_file.insert_sqls {
+                queries.push((
+                    insert_sql.table.clone(),
+                    Some(insert_sql.record_index),
+                    sqlx::query(insert_sql.sql.as_str()).bind_all(arguments(&insert_sql.params)),
+                ));
+            }
+        }
+
+        // Sequences are moved past the largest value, so the next row does not collide with fixtures.
+        let mut setval_queries = vec![];
+        for table in fixture_files.iter().flat_map(|x| x.tables.iter()) {
+            for column in self.serial_columns.get(table).into_iter().flatten() {
+                let quoted_table = quote_table(table, QUOTE)?;
+                let sql = format!(
+                    "SELECT setval(pg_get_serial_sequence($1, $2), COALESCE(MAX({}), 0) + 1, false) FROM {}",
+                    quote_identifier(column, QUOTE)?,
+                    quoted_table
+                );
+                setval_queries.push((table.clone(), sql, quoted_table, column.clone()));
+            }
+        }
+        for (table, sql, quoted_table, column) in &setval_queries {
+            queries.push((
+                table.clone(),
+                None,
+                sqlx::query(sql.as_str()).bind(quoted_table).bind(column),
+            ));
         }
+
+        for (table, record_index, query) in queries {
+            if let Err(source) = query.execute(&mut *conn).await {
+                return Err(Error::Query {
+                    table,
+                    record_index,
+                    source,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// SQL NULL for a column of any type.
+/// A NULL bound as `text` is rejected for non-text columns, so the type is left to PostgreSQL to infer.
+struct Null;
+
+impl Type<P> for Null {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("unknown")
+    }
+}
+
+impl Encode<P> for Null {
+    fn encode(&self, _buf: &mut PgRawBuffer) {}
+
+    fn encode_nullable(&self, _buf: &mut PgRawBuffer) -> IsNull {
+        IsNull::Yes
+    }
+}
+
+/// JSON text for a `json` or `jsonb` column.
+/// It is sent as `jsonb`, which PostgreSQL casts to `json` on assignment.
+struct Jsonb<'a>(&'a str);
+
+impl Type<P> for Jsonb<'_> {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("jsonb")
+    }
+}
+
+impl Encode<P> for Jsonb<'_> {
+    fn encode(&self, buf: &mut PgRawBuffer) {
+        // The binary format of `jsonb` is a version number followed by the JSON text.
+        buf.push(1);
+        buf.extend_from_slice(self.0.as_bytes());
+    }
+}
+
+/// A label of an enum type.
+/// The enum type is left to PostgreSQL to infer, like [Null].
+struct Label<'a>(&'a str);
+
+impl Type<P> for Label<'_> {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("unknown")
+    }
+}
+
+impl Encode<P> for Label<'_> {
+    // The binary format of an enum is its label.
+    fn encode(&self, buf: &mut PgRawBuffer) {
+        buf.extend_from_slice(self.0.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::column_type::ColumnType;
+    use crate::postgresql::helper::column_type;
+
+    #[test]
+    fn test_column_type() {
+        assert_eq!(column_type("smallint", None), ColumnType::signed(16));
+        assert_eq!(column_type("numeric", None), ColumnType::Decimal);
+        assert_eq!(
+            column_type("timestamp with time zone", None),
+            ColumnType::Datetime
+        );
+        assert_eq!(column_type("character varying", None), ColumnType::Text);
+        assert_eq!(
+            column_type("USER-DEFINED", Some(vec!["low".to_string()])),
+            ColumnType::Enum(vec!["low".to_string()])
+        );
+        assert_eq!(column_type("USER-DEFINED", None), ColumnType::Other);
+        assert_eq!(column_type("uuid", None), ColumnType::Other);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "postgresql")]
+mod postgresql_tests {
+    use crate::cleanup::Cleanup;
+    use crate::fixture_file::FixtureFile;
+    use crate::helper::Database as DB;
+    use crate::postgresql::helper::PostgreSql;
+    use crate::postgresql::loader::PostgresLoader;
+    use chrono::{prelude::*, NaiveDate, Utc};
+    use sqlx::{cursor::Cursor, PgConnection, PgPool, Postgres as P, Row};
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_with_transaction() -> anyhow::Result<()> {
+        let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let fixture_file_path = file_path.clone();
+        let mut file = File::create(file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          description: fizz
+          done: false
+          progress: 10.5
+          created_at: 2020/01/01 01:01:01
+        - id: 2
+          description: buzz
+          done: true
+          progress: ~
+          created_at: null"#
+        )
+        .unwrap();
+
+        let mut loader = PostgresLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.helper = Some(Box::new(PostgreSql::default()));
+        let fixture_file = FixtureFile::<Utc> {
+            path: fixture_file_path.to_str().unwrap().to_string(),
+            file_name: fixture_file_path
+                .clone()
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string(),
+            content: File::open(fixture_file_path).unwrap(),
+            tables: vec![],
+            insert_sqls: vec![],
+        };
+        loader.fixture_files = vec![fixture_file];
+        loader.build_insert_sqls()?;
+        let result = loader
+            .helper
+            .unwrap()
+            .with_transaction(&pool, &loader.fixture_files, Cleanup::Delete, false)
+            .await;
+
+        if let Err(err) = result {
+            panic!("test error: {}", err)
+        };
+
+        let mut cursor = sqlx::query(
+            "SELECT id, description, done, progress, created_at FROM todos ORDER BY id",
+        )
+        .fetch(&pool);
+        let row = cursor.next().await?.unwrap();
+        let id: i64 = row.get("id");
+        let description: String = row.get("description");
+        let done: bool = row.get("done");
+        let progress: f32 = row.get("progress");
+        let created_at: NaiveDateTime = row.get("created_at");
+        assert_eq!(id, 1);
+        assert_eq!(description, "fizz");
+        assert!(!done);
+        assert_eq!(progress, 10.5);
+        assert_eq!(
+            created_at,
+            NaiveDate::from_ymd_opt(2020, 1, 1)
+                .unwrap()
+                .and_hms_opt(1, 1, 1)
+                .unwrap()
+        );
+        let row = cursor.next().await?.unwrap();
+        let progress: Option<f32> = row.get("progress");
+        let created_at: Option<NaiveDateTime> = row.get("created_at");
+        assert_eq!(progress, None);
+        assert_eq!(created_at, None);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_checksum() -> anyhow::Result<()> {
+        let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+        let helper = PostgreSql::default();
+        sqlx::query("DROP TABLE IF EXISTS fingerprints")
+            .execute(&pool)
+            .await?;
+        sqlx::query("CREATE TABLE fingerprints (id INT, name TEXT)")
+            .execute(&pool)
+            .await?;
+        let tables = vec!["fingerprints".to_string()];
+        let empty = DB::<P, PgConnection, Utc, Utc>::fingerprint(&helper, &pool, &tables).await?;
+        sqlx::query("INSERT INTO fingerprints VALUES (1, 'fizz')")
+            .execute(&pool)
+            .await?;
+        let inserted =
+            DB::<P, PgConnection, Utc, Utc>::fingerprint(&helper, &pool, &tables).await?;
+        sqlx::query("UPDATE fingerprints SET name = 'buzz'")
+            .execute(&pool)
+            .await?;
+        let updated = DB::<P, PgConnection, Utc, Utc>::fingerprint(&helper, &pool, &tables).await?;
+        assert_ne!(empty, inserted);
+        assert_ne!(inserted, updated);
+        sqlx::query("DROP TABLE fingerprints")
+            .execute(&pool)
+            .await?;
+
+        DB::<P, PgConnection, Utc, Utc>::save_checksum(&helper, &pool, "fizz").await?;
+        assert_eq!(
+            DB::<P, PgConnection, Utc, Utc>::load_checksum(&helper, &pool).await?,
+            Some("fizz".to_string())
+        );
         Ok(())
     }
 }
@@ -1,32 +1,154 @@
+use crate::error::Error;
 use crate::loader::Loader;
 use crate::postgresql::helper;
 use chrono::{Offset, TimeZone};
 use sqlx::{PgConnection, Postgres};
 use std::fmt::Display;
 
-// TODO: Complete this type.
 /// An alias for [Loader](crate::loader::Loader), specialized for **PostgreSQL**.
-pub(crate) type PglLoader<O, Tz> = Loader<Postgres, PgConnection, O, Tz>;
+pub type PostgresLoader<O, Tz> = Loader<Postgres, PgConnection, O, Tz>;
 
 impl<O, Tz> PostgresLoader<O, Tz>
 where
     O: Offset + Display + Send + Sync + 'static,
-    Tz: TimeZone<Offset = O> + Sync + Send + 'static,
+    Tz: TimeZone<Offset = O> + Send + Sync + 'static,
 {
-    pub async fn new<F>(options: F) -> anyhow::Result<PostgresLoader<O, Tz>>
+    /// Creates a [Loader](crate::loader::Loader), specialized for **PostgreSQL** and Set options.
+    ///
+    /// # Example
+    /// ```rust
+    /// #[cfg(test)]
+    /// mod tests {
+    ///     use testfixtures::PostgresLoader;
+    ///     #[async_std::test]
+    ///     async fn test_something() -> anyhow::Result<()> {
+    ///         let loader = PostgresLoader::new(|cfg| {
+    ///             //...
+    ///         })
+    ///         .await?;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    pub async fn new<F>(options: F) -> Result<PostgresLoader<O, Tz>, Error>
     where
         F: FnOnce(&mut PostgresLoader<O, Tz>),
     {
         let mut loader = Self::default();
         options(&mut loader);
+        if loader.location.is_none() {
+            return Err(Error::MissingLocation);
+        }
+        if loader.pool.is_none() {
+            return Err(Error::MissingPool);
+        }
         loader.helper = Some(Box::new(helper::PostgreSql::default()));
-        loader.build_insert_sqls();
-        loader
-            .helper
-            .as_mut()
-            .unwrap()
-            .init(loader.pool.as_ref().unwrap())
-            .await?;
+        loader.prepare().await?;
         Ok(loader)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::postgresql::helper::PostgreSql;
+    use crate::postgresql::loader::PostgresLoader;
+    use chrono::Utc;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn it_returns_option_error() -> anyhow::Result<()> {
+        let result = PostgresLoader::<Utc, Utc>::new(|cfg| {
+            cfg.skip_test_database_check();
+        })
+        .await;
+        assert!(result.is_err());
+        if let Err(err) = result {
+            assert_eq!(err.to_string(), "testfixtures: you need a location");
+        }
+
+        let result = PostgresLoader::<Utc, Utc>::new(|cfg| {
+            cfg.location(Utc);
+        })
+        .await;
+        assert!(result.is_err());
+        if let Err(err) = result {
+            assert_eq!(err.to_string(), "testfixtures: you need a pool");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_insert_sqls() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let mut file = File::create(&file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          description: RAW='fizz?'
+          created_at: 2020/01/01 01:01:01
+        - id: 2
+          description: RAW='buzz?'
+          created_at: 2020/01/01 02:02:02"#
+        )?;
+
+        let mut loader = PostgresLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.helper = Some(Box::new(PostgreSql::default()));
+        loader.files(vec![file_path.to_str().unwrap()]);
+        loader.build_insert_sqls()?;
+        assert_eq!(
+            loader.fixture_files[0].insert_sqls[0].sql,
+            r#"INSERT INTO "todos" ("id", "description", "created_at") VALUES ($1, 'fizz?', $2), ($3, 'buzz?', $4)"#
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "postgresql")]
+mod postgresql_tests {
+    use crate::postgresql::loader::PostgresLoader;
+    use chrono::Utc;
+    use sqlx::PgPool;
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_new() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let fixture_file_path = file_path.clone();
+        let mut file = File::create(file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          description: fizz
+          created_at: 2020/01/01 01:01:01"#
+        )?;
+
+        let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+        let loader = PostgresLoader::new(|cfg| {
+            cfg.location(Utc);
+            cfg.database(pool);
+            cfg.skip_test_database_check();
+            cfg.files(vec![fixture_file_path.to_str().unwrap()]);
+        })
+        .await?;
+
+        assert_eq!(loader.location.unwrap(), Utc);
+        assert!(loader.pool.is_some());
+        assert!(loader.skip_test_database_check);
+        assert!(loader.helper.is_some());
+        assert_eq!(loader.fixture_files.len(), 1);
+        Ok(())
+    }
+}
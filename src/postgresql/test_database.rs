@@ -0,0 +1,104 @@
+use crate::error::Error;
+use crate::helper::quote_identifier;
+use crate::postgresql::loader::PostgresLoader;
+use crate::test_database::{database_name, replace_database, Provision, TestDatabase};
+use async_trait::async_trait;
+use chrono::{Offset, TimeZone};
+use sqlx::{Connect, Connection, PgConnection};
+use std::fmt::Display;
+
+/// The character to quote identifiers.
+const QUOTE: char = '"';
+
+/// The database to connect to while creating and dropping test databases,
+/// since a template database can not have other connections.
+const MAINTENANCE_DATABASE: &str = "postgres";
+
+/// An alias for [TestDatabase](crate::TestDatabase), specialized for **PostgreSQL**.
+pub type PostgresTestDatabase = TestDatabase<PgConnection>;
+
+#[async_trait]
+impl Provision for PgConnection {
+    async fn create_database(template_url: &str, name: &str) -> Result<(), Error> {
+        let mut conn =
+            PgConnection::connect(replace_database(template_url, MAINTENANCE_DATABASE)).await?;
+        sqlx::query(&format!(
+            "CREATE DATABASE {} TEMPLATE {}",
+            quote_identifier(name, QUOTE)?,
+            quote_identifier(database_name(template_url), QUOTE)?
+        ))
+        .execute(&mut conn)
+        .await?;
+        conn.close().await?;
+        Ok(())
+    }
+
+    async fn drop_database(template_url: &str, name: &str) -> Result<(), Error> {
+        let mut conn =
+            PgConnection::connect(replace_database(template_url, MAINTENANCE_DATABASE)).await?;
+        // A database with open connections can not be dropped.
+        sqlx::query(
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = $1 AND pid <> pg_backend_pid()",
+        )
+        .bind(name)
+        .execute(&mut conn)
+        .await?;
+        sqlx::query(&format!(
+            "DROP DATABASE IF EXISTS {}",
+            quote_identifier(name, QUOTE)?
+        ))
+        .execute(&mut conn)
+        .await?;
+        conn.close().await?;
+        Ok(())
+    }
+}
+
+impl PostgresTestDatabase {
+    /// Create a database from the template database at `template_url`, and load fixtures into it.
+    /// The template database can not have other connections while it is copied.
+    ///
+    /// `options` sets options of the [Loader](crate::loader::Loader) except the database,
+    /// and the test database check is skipped since the database is created here.
+    ///
+    /// # Example
+    /// ```rust
+    /// #[cfg(test)]
+    /// mod tests {
+    ///     use chrono::Utc;
+    ///     use testfixtures::PostgresTestDatabase;
+    ///     #[async_std::test]
+    ///     async fn test_something() -> anyhow::Result<()> {
+    ///         let database = PostgresTestDatabase::new("postgres://postgres@127.0.0.1:5433/test_template", |cfg| {
+    ///             cfg.location(Utc);
+    ///             cfg.paths(vec!["fixtures/todos.yml"]);
+    ///         })
+    ///         .await?;
+    ///         let pool = database.pool();
+    ///         // run your tests
+    ///         database.close().await?;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    pub async fn new<O, Tz, F>(
+        template_url: &str,
+        options: F,
+    ) -> Result<PostgresTestDatabase, Error>
+    where
+        O: Offset + Display + Send + Sync + 'static,
+        Tz: TimeZone<Offset = O> + Send + Sync + 'static,
+        F: FnOnce(&mut PostgresLoader<O, Tz>),
+    {
+        let database = Self::create(template_url).await?;
+        let pool = database.pool().clone();
+        let loader = PostgresLoader::new(|cfg| {
+            options(cfg);
+            cfg.database(pool);
+            cfg.skip_test_database_check();
+        })
+        .await?;
+        loader.load().await?;
+        Ok(database)
+    }
+}
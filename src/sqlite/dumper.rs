@@ -0,0 +1,121 @@
+use crate::dumper::{split_table, Dump};
+use crate::error::Error;
+use crate::helper::{quote_identifier, quote_table};
+use async_trait::async_trait;
+use sqlx::cursor::Cursor;
+use sqlx::sqlite::SqliteQueryAs;
+use sqlx::{Row, SqlitePool};
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+
+/// The character to quote identifiers.
+const QUOTE: char = '"';
+
+#[async_trait]
+impl Dump for SqlitePool {
+    async fn records(&self, table: &str) -> Result<Vec<Yaml>, Error> {
+        // A schema qualifies the pragma rather than the table name.
+        let pragma = match split_table(table) {
+            (Some(schema), name) => format!(
+                "PRAGMA {}.table_info({})",
+                quote_identifier(schema, QUOTE)?,
+                quote_identifier(name, QUOTE)?
+            ),
+            (None, name) => format!("PRAGMA table_info({})", quote_identifier(name, QUOTE)?),
+        };
+        let mut columns: Vec<(i32, String, String, i32, Option<String>, i32)> =
+            sqlx::query_as(pragma.as_str()).fetch_all(self).await?;
+        columns.sort_by_key(|x| x.0);
+
+        // Values are read with their storage classes, since the declared types of columns are only affinities.
+        let values = columns
+            .iter()
+            .map(|x| {
+                let column = quote_identifier(&x.1, QUOTE)?;
+                Ok(format!(
+                    "typeof({0}), CASE WHEN typeof({0}) = 'blob' THEN hex({0}) ELSE CAST({0} AS TEXT) END",
+                    column
+                ))
+            })
+            .collect::<Result<Vec<String>, Error>>()?;
+        let mut sql = format!(
+            "SELECT {} FROM {}",
+            values.join(", "),
+            quote_table(table, QUOTE)?
+        );
+        let mut primary_key: Vec<&(i32, String, String, i32, Option<String>, i32)> =
+            columns.iter().filter(|x| x.5 > 0).collect();
+        if !primary_key.is_empty() {
+            primary_key.sort_by_key(|x| x.5);
+            let primary_key = primary_key
+                .iter()
+                .map(|x| quote_identifier(&x.1, QUOTE))
+                .collect::<Result<Vec<String>, Error>>()?;
+            sql = format!("{} ORDER BY {}", sql, primary_key.join(", "));
+        }
+
+        let mut records = vec![];
+        let mut cursor = sqlx::query(sql.as_str()).fetch(self);
+        while let Some(row) = cursor.next().await? {
+            let mut record = Hash::new();
+            for (index, column) in columns.iter().enumerate() {
+                let storage_class: String = row.try_get(index * 2)?;
+                let value: Option<String> = row.try_get(index * 2 + 1)?;
+                record.insert(
+                    Yaml::String(column.1.clone()),
+                    value_to_yaml(&storage_class, value),
+                );
+            }
+            records.push(Yaml::Hash(record));
+        }
+        Ok(records)
+    }
+}
+
+/// Convert the text form of a value to YAML by its storage class.
+fn value_to_yaml(storage_class: &str, value: Option<String>) -> Yaml {
+    let value = match value {
+        Some(value) => value,
+        None => return Yaml::Null,
+    };
+    match storage_class {
+        "integer" => match value.parse::<i64>() {
+            Ok(value) => Yaml::Integer(value),
+            Err(_) => Yaml::String(value),
+        },
+        "real" => match value.as_str() {
+            "Inf" => Yaml::Real(".inf".to_string()),
+            "-Inf" => Yaml::Real("-.inf".to_string()),
+            _ => Yaml::Real(value),
+        },
+        "blob" => Yaml::String(format!("RAW=X'{}'", value)),
+        _ => Yaml::String(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sqlite::dumper::value_to_yaml;
+    use yaml_rust::Yaml;
+
+    #[test]
+    fn test_value_to_yaml() {
+        assert_eq!(value_to_yaml("null", None), Yaml::Null);
+        assert_eq!(
+            value_to_yaml("integer", Some("1".to_string())),
+            Yaml::Integer(1)
+        );
+        assert_eq!(
+            value_to_yaml("real", Some("10.5".to_string())),
+            Yaml::Real("10.5".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("blob", Some("CAFE".to_string())),
+            Yaml::String("RAW=X'CAFE'".to_string())
+        );
+        assert_eq!(
+            value_to_yaml("text", Some("2020-01-01 01:01:01".to_string())),
+            Yaml::String("2020-01-01 01:01:01".to_string())
+        );
+    }
+}
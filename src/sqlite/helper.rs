@@ -0,0 +1,524 @@
+use crate::cleanup::Cleanup;
+use crate::column_type::{Column, ColumnType};
+use crate::error::Error;
+use crate::fixture_file::{FixtureFile, SqlParam};
+use crate::helper::{
+    cleanup_queries, quote_identifier, quote_table, Database as DB, CHECKSUM_TABLE,
+};
+use async_trait::async_trait;
+use chrono::{Offset, TimeZone};
+use sqlx::sqlite::SqliteQueryAs;
+use sqlx::{
+    arguments::Arguments, sqlite::SqliteArguments, Connection, Sqlite as S, SqliteConnection,
+    SqlitePool,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::Path;
+
+/// The character to quote identifiers.
+const QUOTE: char = '"';
+
+/// **SQLite** helper.
+#[derive(Default)]
+pub struct Sqlite {
+    pub table_names: Vec<String>,
+    /// Primary key columns of each table, by table name and by name qualified with `main`.
+    pub primary_keys: HashMap<String, Vec<String>>,
+    /// Columns of each table, keyed like `primary_keys`.
+    pub columns: Option<HashMap<String, Vec<Column>>>,
+}
+
+impl Sqlite {
+    /// Create the bookkeeping table for checksums if it does not exist, and get its quoted name.
+    async fn checksum_table(pool: &SqlitePool) -> Result<String, Error> {
+        let table = quote_identifier(CHECKSUM_TABLE, QUOTE)?;
+        sqlx::query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (checksum VARCHAR(64) NOT NULL)",
+                table
+            )
+            .as_str(),
+        )
+        .execute(pool)
+        .await?;
+        Ok(table)
+    }
+
+    /// Get primary key columns and columns of `tables`.
+    #[allow(clippy::type_complexity)]
+    async fn table_info(
+        pool: &SqlitePool,
+        tables: &[String],
+    ) -> Result<(HashMap<String, Vec<String>>, HashMap<String, Vec<Column>>), Error> {
+        let mut primary_keys = HashMap::new();
+        let mut table_columns = HashMap::new();
+        for table in tables {
+            let sql = format!("PRAGMA table_info({})", quote_identifier(table, QUOTE)?);
+            let mut columns: Vec<(i32, String, String, i32, Option<String>, i32)> =
+                sqlx::query_as(sql.as_str()).fetch_all(pool).await?;
+            // An INTEGER PRIMARY KEY is the rowid, which is assigned when it is left out or NULL.
+            let rowid = match columns.iter().filter(|x| x.5 > 0).collect::<Vec<_>>()[..] {
+                [column] if column.2.eq_ignore_ascii_case("INTEGER") => Some(column.1.clone()),
+                _ => None,
+            };
+            let info: Vec<Column> = columns
+                .iter()
+                .map(|(_, name, declared_type, not_null, default, _)| {
+                    let is_rowid = rowid.as_ref() == Some(name);
+                    Column {
+                        name: name.clone(),
+                        column_type: column_type(declared_type),
+                        nullable: *not_null == 0 || is_rowid,
+                        has_default: default.is_some() || is_rowid,
+                        // SQLite does not enforce lengths of strings.
+                        max_length: None,
+                    }
+                })
+                .collect();
+            table_columns.insert(format!("main.{}", table), info.clone());
+            table_columns.insert(table.clone(), info);
+            // `pk` is the position of the column in the primary key, or 0.
+            columns.retain(|x| x.5 > 0);
+            columns.sort_by_key(|x| x.5);
+            let columns: Vec<String> = columns.into_iter().map(|x| x.1).collect();
+            primary_keys.insert(format!("main.{}", table), columns.clone());
+            primary_keys.insert(table.clone(), columns);
+        }
+        Ok((primary_keys, table_columns))
+    }
+}
+
+/// Get the type of a column from its declared type.
+/// SQLite stores any value in any column, so names which are not common types follow the
+/// [type affinity](https://www.sqlite.org/datatype3.html#determination_of_column_affinity) rules.
+fn column_type(declared_type: &str) -> ColumnType {
+    let declared_type = declared_type.to_uppercase();
+    let name = declared_type.split('(').next().unwrap_or_default().trim();
+    match name {
+        "BOOLEAN" | "BOOL" => ColumnType::Boolean,
+        "DATE" => ColumnType::Date,
+        "TIME" => ColumnType::Time,
+        "DATETIME" | "TIMESTAMP" => ColumnType::Datetime,
+        "JSON" => ColumnType::Json,
+        "DECIMAL" | "NUMERIC" => ColumnType::Decimal,
+        // A column without a type takes values as they are.
+        "" => ColumnType::Other,
+        _ if name.contains("INT") => ColumnType::signed(64),
+        _ if ["CHAR", "CLOB", "TEXT"].iter().any(|x| name.contains(x)) => ColumnType::Text,
+        _ if name.contains("BLOB") => ColumnType::Binary,
+        _ if ["REAL", "FLOA", "DOUB"].iter().any(|x| name.contains(x)) => ColumnType::Float,
+        _ => ColumnType::Other,
+    }
+}
+
+/// Bind parameters for a SQLite query.
+fn arguments<'a, Tz>(params: impl IntoIterator<Item = &'a SqlParam<Tz>>) -> SqliteArguments
+where
+    Tz: TimeZone + Send + Sync + 'a,
+{
+    let mut args = SqliteArguments::default();
+    for param in params {
+        match param {
+            SqlParam::String(param) => args.add(param),
+            SqlParam::Integer(param) => args.add(param),
+            // There are no unsigned integer types, so larger values are left to the database to convert.
+            SqlParam::UnsignedInteger(param) => match i64::try_from(*param) {
+                Ok(param) => args.add(param),
+                Err(_) => args.add(param.to_string()),
+            },
+            SqlParam::Datetime(param) => {
+                args.add(param.naive_local().format("%Y-%m-%d %H:%M:%S").to_string())
+            }
+            SqlParam::Float(param) => args.add(param),
+            // SQLite has no decimal type. Column affinity converts the text.
+            SqlParam::Decimal(param) => args.add(param),
+            SqlParam::Boolean(param) => args.add(param),
+            SqlParam::Date(param) => args.add(param.to_string()),
+            SqlParam::Time(param) => args.add(param.to_string()),
+            SqlParam::Binary(param) => args.add(param),
+            SqlParam::Json(param) | SqlParam::Enum(param) => args.add(param),
+            SqlParam::Null => args.add(None::<String>),
+        }
+    }
+    args
+}
+
+#[async_trait]
+impl<O, Tz> DB<S, SqliteConnection, O, Tz> for Sqlite
+where
+    O: Offset + Sync + Send + 'static,
+    Tz: TimeZone<Offset = O> + Send + Sync + 'static,
+{
+    /// Initialize SQLite struct.
+    async fn init(&mut self, pool: &SqlitePool) -> Result<(), Error> {
+        self.table_names = DB::<S, SqliteConnection, O, Tz>::table_names(self, pool).await?;
+        let (primary_keys, columns) = Self::table_info(pool, &self.table_names).await?;
+        self.primary_keys = primary_keys;
+        self.columns = Some(columns);
+        Ok(())
+    }
+
+    /// Get database name from the path of the main database file.
+    /// A temporary or in-memory database has no file and is named `:memory:`.
+    async fn database_name(&self, pool: &SqlitePool) -> Result<String, Error> {
+        let databases: Vec<(i32, String, String)> = sqlx::query_as("PRAGMA database_list")
+            .fetch_all(pool)
+            .await?;
+        let file = databases
+            .into_iter()
+            .find(|(_, name, _)| name == "main")
+            .map(|(_, _, file)| file)
+            .unwrap_or_default();
+        match Path::new(file.as_str()).file_stem() {
+            Some(stem) => Ok(stem.to_string_lossy().to_string()),
+            None => Ok(":memory:".to_string()),
+        }
+    }
+
+    /// SQLite is a file on localhost.
+    async fn server_address(&self, _pool: &SqlitePool) -> Result<Option<String>, Error> {
+        Ok(Some("localhost".to_string()))
+    }
+
+    /// Get names of all tables in the main database.
+    async fn table_names(&self, pool: &SqlitePool) -> Result<Vec<String>, Error> {
+        let tables: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT name
+            FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+            ORDER BY name
+        "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(tables
+            .into_iter()
+            .map(|x| x.0)
+            .filter(|x| x != CHECKSUM_TABLE)
+            .collect())
+    }
+
+    /// Get the rows in `tables` as text.
+    // SQLite has no hash function, so the loader hashes the rows.
+    async fn fingerprint(&self, pool: &SqlitePool, tables: &[String]) -> Result<String, Error> {
+        let mut fingerprint = vec![];
+        for table in tables {
+            let quoted_table = quote_table(table, QUOTE)?;
+            // A schema qualifies the pragma rather than the table name.
+            let pragma = match table.split_once('.') {
+                Some((schema, name)) => format!(
+                    "PRAGMA {}.table_info({})",
+                    quote_identifier(schema, QUOTE)?,
+                    quote_identifier(name, QUOTE)?
+                ),
+                None => format!("PRAGMA table_info({})", quoted_table),
+            };
+            let columns: Vec<(i32, String, String, i32, Option<String>, i32)> =
+                sqlx::query_as(pragma.as_str()).fetch_all(pool).await?;
+            let row = columns
+                .iter()
+                .map(|x| Ok(format!("quote({})", quote_identifier(&x.1, QUOTE)?)))
+                .collect::<Result<Vec<String>, Error>>()?
+                .join(" || ',' || ");
+            let sql = format!(
+                "SELECT group_concat(x, ';') FROM (SELECT {} AS x FROM {} ORDER BY 1)",
+                row, quoted_table
+            );
+            let rows: (Option<String>,) = sqlx::query_as(sql.as_str()).fetch_one(pool).await?;
+            fingerprint.push(format!("{}={}", table, rows.0.unwrap_or_default()));
+        }
+        Ok(fingerprint.join(";"))
+    }
+
+    /// Get the checksum saved by the last load.
+    async fn load_checksum(&self, pool: &SqlitePool) -> Result<Option<String>, Error> {
+        let table = Self::checksum_table(pool).await?;
+        let checksum: Option<(String,)> =
+            sqlx::query_as(format!("SELECT checksum FROM {}", table).as_str())
+                .fetch_optional(pool)
+                .await?;
+        Ok(checksum.map(|x| x.0))
+    }
+
+    /// Save the checksum of a load in the bookkeeping table.
+    async fn save_checksum(&self, pool: &SqlitePool, checksum: &str) -> Result<(), Error> {
+        let table = Self::checksum_table(pool).await?;
+        let mut tx = pool.begin().await?;
+        sqlx::query(format!("DELETE FROM {}", table).as_str())
+            .execute(&mut tx)
+            .await?;
+        sqlx::query(format!("INSERT INTO {} (checksum) VALUES (?)", table).as_str())
+            .bind(checksum)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn schema(&self) -> Option<&HashMap<String, Vec<Column>>> {
+        self.columns.as_ref()
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> Result<String, Error> {
+        quote_identifier(identifier, QUOTE)
+    }
+
+    // SQLITE_MAX_VARIABLE_NUMBER defaults to 999 before SQLite 3.32.0.
+    fn max_bind_params(&self) -> usize {
+        999
+    }
+
+    /// Execute SQL queries in a transaction for SQLite.
+    async fn with_transaction(
+        &self,
+        pool: &SqlitePool,
+        fixture_files: &[FixtureFile<Tz>],
+        cleanup: Cleanup,
+        clean_all_tables: bool,
+    ) -> Result<(), Error> {
+        // `PRAGMA foreign_keys` is a no-op inside a transaction,
+        // so it has to be toggled on the connection before BEGIN.
+        let mut conn = pool.acquire().await?;
+        let foreign_keys: (i32,) = sqlx::query_as("PRAGMA foreign_keys")
+            .fetch_one(&mut conn)
+            .await?;
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut conn)
+            .await?;
+        let mut tx = conn.begin().await?;
+        let result = self
+            .execute(&mut tx, fixture_files, cleanup, clean_all_tables)
+            .await;
+        let mut conn = match result {
+            Ok(_) => tx.commit().await?,
+            Err(err) => {
+                // The error of the query is returned even if the rollback fails.
+                if let Ok(mut conn) = tx.rollback().await {
+                    let _ =
+                        sqlx::query(format!("PRAGMA foreign_keys = {}", foreign_keys.0).as_str())
+                            .execute(&mut conn)
+                            .await;
+                }
+                return Err(err);
+            }
+        };
+        sqlx::query(format!("PRAGMA foreign_keys = {}", foreign_keys.0).as_str())
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn execute(
+        &self,
+        conn: &mut SqliteConnection,
+        fixture_files: &[FixtureFile<Tz>],
+        cleanup: Cleanup,
+        clean_all_tables: bool,
+    ) -> Result<(), Error> {
+        // `PRAGMA foreign_keys` can not be changed in a transaction, but foreign keys can be checked at commit.
+        sqlx::query("PRAGMA defer_foreign_keys = ON")
+            .execute(&mut *conn)
+            .await?;
+
+        let mut queries = vec![];
+        // SQLite has no TRUNCATE. DELETE without WHERE is optimized to drop the rows at once.
+        let cleanup_queries = cleanup_queries(
+            self,
+            fixture_files,
+            &self.table_names,
+            &self.primary_keys,
+            cleanup,
+            clean_all_tables,
+            |x| quote_table(x, QUOTE),
+            |tables| {
+                tables
+                    .iter()
+                    .map(|(table, quoted)| (table.clone(), format!("DELETE FROM {}", quoted)))
+                    .collect()
+            },
+        )?;
+        for (table, sql, params) in &cleanup_queries {
+            queries.push((
+                table.clone(),
+                None,
+                sqlx::query(sql.as_str()).bind_all(arguments(params.iter().copied())),
+            ));
+        }
+
+        for fixtures_file in fixture_files {
+            for sql in &fixtures_file.insert_sqls {
+                queries.push((
+                    sql.table.clone(),
+                    Some(sql.record_index),
+                    sqlx::query(sql.sql.as_str()).bind_all(arguments(&sql.params)),
+                ));
+            }
+        }
+
+        for (table, record_index, query) in queries {
+            if let Err(source) = query.execute(&mut *conn).await {
+                return Err(Error::Query {
+                    table,
+                    record_index,
+                    source,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cleanup::Cleanup;
+    use crate::column_type::ColumnType;
+    use crate::fixture_file::FixtureFile;
+    use crate::helper::Database as DB;
+    use crate::sqlite::helper::{column_type, Sqlite};
+    use crate::sqlite::loader::SqliteLoader;
+    use chrono::{prelude::*, NaiveDate, Utc};
+    use sqlx::{cursor::Cursor, sqlite::SqliteQueryAs, Row, SqlitePool};
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_column_type() {
+        assert_eq!(column_type("INTEGER"), ColumnType::signed(64));
+        assert_eq!(column_type("varchar(32)"), ColumnType::Text);
+        assert_eq!(column_type("DATETIME"), ColumnType::Datetime);
+        assert_eq!(column_type("DECIMAL(12, 2)"), ColumnType::Decimal);
+        assert_eq!(column_type("DOUBLE PRECISION"), ColumnType::Float);
+        assert_eq!(column_type(""), ColumnType::Other);
+    }
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_database_name() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let database_path = dir.path().join("app_test.db");
+        let pool =
+            SqlitePool::new(format!("sqlite://{}", database_path.to_str().unwrap()).as_str())
+                .await?;
+        let name = DB::<_, _, Utc, Utc>::database_name(&Sqlite::default(), &pool).await?;
+        assert_eq!(name, "app_test");
+
+        let pool = SqlitePool::builder().max_size(1).build("sqlite:").await?;
+        let name = DB::<_, _, Utc, Utc>::database_name(&Sqlite::default(), &pool).await?;
+        assert_eq!(name, ":memory:");
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_table_names() -> anyhow::Result<()> {
+        let pool = SqlitePool::builder().max_size(1).build("sqlite:").await?;
+        sqlx::query(
+            r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT);
+            CREATE TABLE todos (id INTEGER PRIMARY KEY NOT NULL);"#,
+        )
+        .execute(&pool)
+        .await?;
+        let mut helper = Sqlite::default();
+        DB::<_, _, Utc, Utc>::init(&mut helper, &pool).await?;
+        assert_eq!(helper.table_names, vec!["todos", "users"]);
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_with_transaction() -> anyhow::Result<()> {
+        let pool = SqlitePool::builder().max_size(1).build("sqlite:").await?;
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&pool)
+            .await?;
+        sqlx::query(
+            r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY NOT NULL);
+            CREATE TABLE todos (
+                id INTEGER PRIMARY KEY NOT NULL,
+                user_id INTEGER REFERENCES users (id),
+                description TEXT NOT NULL,
+                done BOOLEAN NOT NULL DEFAULT FALSE,
+                progress REAL,
+                created_at DATETIME
+            );"#,
+        )
+        .execute(&pool)
+        .await?;
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let fixture_file_path = file_path.clone();
+        let mut file = File::create(file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          user_id: 1
+          description: fizz
+          done: false
+          progress: 10.5
+          created_at: 2020/01/01 01:01:01"#
+        )
+        .unwrap();
+
+        let mut loader = SqliteLoader::<Utc, Utc>::default();
+        loader.location(Utc);
+        loader.helper = Some(Box::new(Sqlite::default()));
+        let fixture_file = FixtureFile::<Utc> {
+            path: fixture_file_path.to_str().unwrap().to_string(),
+            file_name: fixture_file_path
+                .clone()
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string(),
+            content: File::open(fixture_file_path).unwrap(),
+            tables: vec![],
+            insert_sqls: vec![],
+        };
+        loader.fixture_files = vec![fixture_file];
+        loader.build_insert_sqls()?;
+        let result = loader
+            .helper
+            .unwrap()
+            .with_transaction(&pool, &loader.fixture_files, Cleanup::Delete, false)
+            .await;
+
+        if let Err(err) = result {
+            panic!("test error: {}", err)
+        };
+
+        let mut cursor =
+            sqlx::query("SELECT id, description, done, progress, created_at FROM todos")
+                .fetch(&pool);
+        let row = cursor.next().await?.unwrap();
+        let id: i32 = row.get("id");
+        let description: String = row.get("description");
+        let done: bool = row.get("done");
+        let progress: f32 = row.get("progress");
+        let created_at: String = row.get("created_at");
+        assert_eq!(id, 1);
+        assert_eq!(description, "fizz");
+        assert!(!done);
+        assert_eq!(progress, 10.5);
+        assert_eq!(
+            NaiveDateTime::parse_from_str(created_at.as_str(), "%Y-%m-%d %H:%M:%S")?,
+            NaiveDate::from_ymd_opt(2020, 1, 1)
+                .unwrap()
+                .and_hms_opt(1, 1, 1)
+                .unwrap()
+        );
+        drop(cursor);
+
+        let foreign_keys: (i32,) = sqlx::query_as("PRAGMA foreign_keys")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(foreign_keys.0, 1);
+        Ok(())
+    }
+}
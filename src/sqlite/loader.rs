@@ -0,0 +1,96 @@
+use crate::error::Error;
+use crate::loader::Loader;
+use crate::sqlite::helper;
+use chrono::{Offset, TimeZone};
+use sqlx::{Sqlite, SqliteConnection};
+use std::fmt::Display;
+
+/// An alias for [Loader](crate::loader::Loader), specialized for **SQLite**.
+pub type SqliteLoader<O, Tz> = Loader<Sqlite, SqliteConnection, O, Tz>;
+
+impl<O, Tz> SqliteLoader<O, Tz>
+where
+    O: Offset + Display + Send + Sync + 'static,
+    Tz: TimeZone<Offset = O> + Send + Sync + 'static,
+{
+    /// Creates a [Loader](crate::loader::Loader), specialized for **SQLite** and Set options.
+    ///
+    /// # Example
+    /// ```rust
+    /// #[cfg(test)]
+    /// mod tests {
+    ///     use testfixtures::SqliteLoader;
+    ///     #[async_std::test]
+    ///     async fn test_something() -> anyhow::Result<()> {
+    ///         let loader = SqliteLoader::new(|cfg| {
+    ///             //...
+    ///         })
+    ///         .await?;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    pub async fn new<F>(options: F) -> Result<SqliteLoader<O, Tz>, Error>
+    where
+        F: FnOnce(&mut SqliteLoader<O, Tz>),
+    {
+        let mut loader = Self::default();
+        options(&mut loader);
+        if loader.location.is_none() {
+            return Err(Error::MissingLocation);
+        }
+        if loader.pool.is_none() {
+            return Err(Error::MissingPool);
+        }
+        loader.helper = Some(Box::new(helper::Sqlite::default()));
+        loader.prepare().await?;
+        Ok(loader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sqlite::loader::SqliteLoader;
+    use chrono::Utc;
+    use sqlx::SqlitePool;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[cfg_attr(feature = "runtime-async-std", async_std::test)]
+    #[cfg_attr(feature = "runtime-tokio", tokio::test)]
+    async fn test_new() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("todos.yml");
+        let fixture_file_path = file_path.clone();
+        let mut file = File::create(file_path)?;
+        writeln!(
+            file,
+            r#"
+        - id: 1
+          description: fizz
+          created_at: 2020/01/01 01:01:01"#
+        )?;
+
+        let pool = SqlitePool::builder().max_size(1).build("sqlite:").await?;
+        sqlx::query(
+            "CREATE TABLE todos (id INTEGER PRIMARY KEY, description TEXT, created_at DATETIME)",
+        )
+        .execute(&pool)
+        .await?;
+        let loader = SqliteLoader::new(|cfg| {
+            cfg.location(Utc);
+            cfg.database(pool);
+            cfg.skip_test_database_check();
+            cfg.files(vec![fixture_file_path.to_str().unwrap()]);
+        })
+        .await?;
+
+        assert_eq!(loader.location.unwrap(), Utc);
+        assert!(loader.pool.is_some());
+        assert!(loader.skip_test_database_check);
+        assert!(loader.helper.is_some());
+        assert_eq!(loader.fixture_files.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod dumper;
+pub mod helper;
+pub mod loader;
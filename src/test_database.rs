@@ -0,0 +1,214 @@
+use crate::error::Error;
+use async_trait::async_trait;
+use sqlx::{Connect, Connection, Pool};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Represents a database connection which can create and drop databases for [TestDatabase](crate::TestDatabase).
+#[async_trait]
+pub trait Provision: Connect + Connection + Sized {
+    /// Create the database `name` with the schema of the database at `template_url`.
+    async fn create_database(template_url: &str, name: &str) -> Result<(), Error>;
+
+    /// Drop the database `name` on the server at `template_url`.
+    async fn drop_database(template_url: &str, name: &str) -> Result<(), Error>;
+}
+
+/// A database created for a single test, which is dropped by [close](TestDatabase::close).
+/// If the guard is dropped without being closed, the database is dropped on a best-effort basis.
+pub struct TestDatabase<C>
+where
+    C: Provision + 'static,
+{
+    pool: Pool<C>,
+    name: String,
+    template_url: String,
+    /// Whether the database has been dropped by `close`.
+    closed: bool,
+}
+
+impl<C> TestDatabase<C>
+where
+    C: Provision + 'static,
+{
+    /// Create a uniquely named database with the schema of the database at `template_url`, and connect to it.
+    pub(crate) async fn create(template_url: &str) -> Result<TestDatabase<C>, Error> {
+        let name = unique_name(database_name(template_url));
+        C::create_database(template_url, &name).await?;
+        let pool = match Pool::new(&replace_database(template_url, &name)).await {
+            Ok(pool) => pool,
+            Err(err) => {
+                // The error of the connection is returned even if the database can not be dropped.
+                let _ = C::drop_database(template_url, &name).await;
+                return Err(err.into());
+            }
+        };
+        Ok(TestDatabase {
+            pool,
+            name,
+            template_url: template_url.to_string(),
+            closed: false,
+        })
+    }
+
+    /// A pool connected to the database.
+    pub fn pool(&self) -> &Pool<C> {
+        &self.pool
+    }
+
+    /// The name of the database.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Close the pool and drop the database.
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.closed = true;
+        self.pool.close().await;
+        C::drop_database(&self.template_url, &self.name).await
+    }
+}
+
+impl<C> Drop for TestDatabase<C>
+where
+    C: Provision + 'static,
+{
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+        let pool = self.pool.clone();
+        let template_url = self.template_url.clone();
+        let name = self.name.clone();
+        // Drop can not await, so the database is dropped on another thread with its own runtime.
+        // Errors can not be returned from here, and `close` should be used to see them.
+        let _ = thread::spawn(move || {
+            block_on(async {
+                pool.close().await;
+                C::drop_database(&template_url, &name).await
+            })
+        })
+        .join();
+    }
+}
+
+/// Run `future` to completion on the runtime testfixtures is built for.
+#[cfg(feature = "runtime-async-std")]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    async_std::task::block_on(future)
+}
+
+/// Run `future` to completion on the runtime testfixtures is built for.
+#[cfg(all(feature = "runtime-tokio", not(feature = "runtime-async-std")))]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .expect("testfixtures: failed to start a runtime")
+        .block_on(future)
+}
+
+/// The maximum length of a database name in bytes.
+/// PostgreSQL truncates longer names to 63 bytes, and MySQL rejects names longer than 64 characters.
+const MAX_NAME_LEN: usize = 63;
+
+/// A name no other test database has, like `test_16a3b9c7e2f0_1234_0`.
+/// `prefix` is shortened so the name fits in [MAX_NAME_LEN] bytes.
+fn unique_name(prefix: &str) -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_nanos())
+        .unwrap_or_default();
+    let suffix = format!(
+        "_{:x}_{}_{}",
+        nanos,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    );
+    let mut len = prefix.len().min(MAX_NAME_LEN.saturating_sub(suffix.len()));
+    while !prefix.is_char_boundary(len) {
+        len -= 1;
+    }
+    format!("{}{}", &prefix[..len], suffix)
+}
+
+/// The range of the database name in `url`, or an empty range where it would be.
+fn database_range(url: &str) -> (usize, usize) {
+    let authority = url.find("://").map_or(0, |x| x + 3);
+    let end = url.find('?').unwrap_or(url.len());
+    match url[authority..end].find('/') {
+        Some(slash) => (authority + slash + 1, end),
+        None => (end, end),
+    }
+}
+
+/// The database name in `url`.
+pub(crate) fn database_name(url: &str) -> &str {
+    let (start, end) = database_range(url);
+    &url[start..end]
+}
+
+/// `url` with its database name replaced by `name`.
+pub(crate) fn replace_database(url: &str, name: &str) -> String {
+    let (start, end) = database_range(url);
+    if start == end && !url[..start].ends_with('/') {
+        return format!("{}/{}{}", &url[..start], name, &url[end..]);
+    }
+    format!("{}{}{}", &url[..start], name, &url[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_database::{database_name, replace_database, unique_name, MAX_NAME_LEN};
+
+    #[test]
+    fn test_database_name() {
+        assert_eq!(
+            database_name("postgres://postgres@127.0.0.1:5433/test"),
+            "test"
+        );
+        assert_eq!(
+            database_name("mysql://root@127.0.0.1:3314/test?ssl-mode=disabled"),
+            "test"
+        );
+        assert_eq!(database_name("postgres://postgres@127.0.0.1:5433"), "");
+    }
+
+    #[test]
+    fn test_replace_database() {
+        assert_eq!(
+            replace_database("postgres://postgres@127.0.0.1:5433/test", "postgres"),
+            "postgres://postgres@127.0.0.1:5433/postgres"
+        );
+        assert_eq!(
+            replace_database("mysql://root@127.0.0.1:3314/test?ssl-mode=disabled", "fizz"),
+            "mysql://root@127.0.0.1:3314/fizz?ssl-mode=disabled"
+        );
+        assert_eq!(
+            replace_database("postgres://postgres@127.0.0.1:5433", "fizz"),
+            "postgres://postgres@127.0.0.1:5433/fizz"
+        );
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let name = unique_name("test");
+        assert!(name.starts_with("test_"));
+        assert_ne!(name, unique_name("test"));
+
+        let template = "a_very_long_template_database_name_for_the_integration_test";
+        let name = unique_name(template);
+        assert!(name.len() <= MAX_NAME_LEN, "{}", name);
+        assert!(name.starts_with("a_very_long_"), "{}", name);
+        assert_ne!(name, unique_name(template));
+
+        // A multibyte character is not split.
+        let name = unique_name(&"ü".repeat(40));
+        assert!(name.len() <= MAX_NAME_LEN, "{}", name);
+        assert!(name.starts_with("üü"), "{}", name);
+    }
+}
@@ -0,0 +1,241 @@
+//! Macros for [testfixtures](https://docs.rs/testfixtures). Use them through `testfixtures`, like `#[testfixtures::test(...)]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Expr, FnArg, Ident, ItemFn, Lit, LitStr, Token};
+
+/// The database a test uses.
+enum Backend {
+    MySql,
+    Postgres,
+    Sqlite,
+}
+
+/// An argument like `db = "DATABASE_URL"`.
+struct Arg {
+    name: Ident,
+    value: Expr,
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(Arg { name, value })
+    }
+}
+
+/// Arguments of [test](macro@test).
+struct Args {
+    fixtures: Vec<LitStr>,
+    db: LitStr,
+    backend: Backend,
+    skip_test_database_check: bool,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut fixtures = None;
+        let mut db = None;
+        let mut backend = Backend::MySql;
+        let mut skip_test_database_check = false;
+        for arg in Punctuated::<Arg, Token![,]>::parse_terminated(input)? {
+            match arg.name.to_string().as_str() {
+                "fixtures" => match &arg.value {
+                    Expr::Array(array) => {
+                        fixtures = Some(
+                            array
+                                .elems
+                                .iter()
+                                .map(lit_str)
+                                .collect::<syn::Result<Vec<LitStr>>>()?,
+                        )
+                    }
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "`fixtures` must be an array of paths",
+                        ))
+                    }
+                },
+                "db" => db = Some(lit_str(&arg.value)?),
+                "backend" => {
+                    let value = lit_str(&arg.value)?;
+                    backend = match value.value().as_str() {
+                        "mysql" => Backend::MySql,
+                        "postgresql" | "postgres" => Backend::Postgres,
+                        "sqlite" => Backend::Sqlite,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                value,
+                                "`backend` must be \"mysql\", \"postgresql\" or \"sqlite\"",
+                            ))
+                        }
+                    }
+                }
+                "skip_test_database_check" => match &arg.value {
+                    Expr::Lit(expr) => match &expr.lit {
+                        Lit::Bool(value) => skip_test_database_check = value.value,
+                        lit => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "`skip_test_database_check` must be a bool",
+                            ))
+                        }
+                    },
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "`skip_test_database_check` must be a bool",
+                        ))
+                    }
+                },
+                name => {
+                    return Err(syn::Error::new_spanned(
+                        &arg.name,
+                        format!("unknown argument `{}`", name),
+                    ))
+                }
+            }
+        }
+        Ok(Args {
+            fixtures: fixtures
+                .ok_or_else(|| syn::Error::new(Span::call_site(), "missing `fixtures` argument"))?,
+            db: db.ok_or_else(|| syn::Error::new(Span::call_site(), "missing `db` argument"))?,
+            backend,
+            skip_test_database_check,
+        })
+    }
+}
+
+fn lit_str(expr: &Expr) -> syn::Result<LitStr> {
+    match expr {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Str(value) => Ok(value.clone()),
+            lit => Err(syn::Error::new_spanned(lit, "expected a string literal")),
+        },
+        expr => Err(syn::Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+/// Marks an async function as a test which loads fixtures before it runs.
+///
+/// The test connects to the database at the URL in the environment variable `db`,
+/// and loads `fixtures` with a loader for `backend` (`"mysql"` by default).
+/// It runs on the runtime testfixtures is built for, async-std or tokio with the `runtime-tokio` feature,
+/// so the test crate does not have to depend on the runtime.
+/// If the function takes an argument, the pool is passed to it.
+///
+/// # Example
+/// ```rust,ignore
+/// #[testfixtures::test(fixtures = ["fixtures/todos.yml"], db = "DATABASE_URL")]
+/// async fn test_something(pool: sqlx::MySqlPool) -> anyhow::Result<()> {
+///     // run your tests
+///     Ok(())
+/// }
+/// ```
+///
+/// # Arguments
+/// - `fixtures`: paths of fixture files or directories.
+/// - `db`: the environment variable with the database URL.
+/// - `backend`(optional): `"mysql"`, `"postgresql"` or `"sqlite"`.
+/// - `skip_test_database_check`(optional): `true` to skip the test database check.
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as Args);
+    let input = parse_macro_input!(item as ItemFn);
+    match expand(args, input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(args: Args, input: ItemFn) -> syn::Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        mut sig,
+        block,
+    } = input;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(
+            sig.fn_token,
+            "the test function must be async",
+        ));
+    }
+    let bind_pool = match sig.inputs.iter().collect::<Vec<&FnArg>>().as_slice() {
+        [] => quote!(),
+        [FnArg::Typed(arg)] => {
+            let pat = &arg.pat;
+            let ty = &arg.ty;
+            quote!(let #pat: #ty = __testfixtures_pool;)
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &sig.inputs,
+                "the test function can only take the pool as an argument",
+            ))
+        }
+    };
+    sig.inputs.clear();
+    let name = sig.ident.clone();
+    let output = sig.output.clone();
+    sig.ident = Ident::new("__testfixtures_test", Span::call_site());
+
+    let (pool, loader) = match args.backend {
+        Backend::MySql => (
+            quote!(::testfixtures::export::sqlx::MySqlPool),
+            quote!(::testfixtures::MySqlLoader),
+        ),
+        Backend::Postgres => (
+            quote!(::testfixtures::export::sqlx::PgPool),
+            quote!(::testfixtures::PostgresLoader),
+        ),
+        Backend::Sqlite => (
+            quote!(::testfixtures::export::sqlx::SqlitePool),
+            quote!(::testfixtures::SqliteLoader),
+        ),
+    };
+    let skip_test_database_check = if args.skip_test_database_check {
+        quote!(cfg.skip_test_database_check();)
+    } else {
+        quote!()
+    };
+    let db = args.db;
+    let fixtures = args.fixtures;
+
+    // `async_std::test` and `tokio::test` name their crates in the code they generate,
+    // so the test is run by the runtime re-exported by testfixtures instead.
+    Ok(quote! {
+        #(#attrs)*
+        #[::core::prelude::v1::test]
+        #vis fn #name() #output {
+            #sig {
+                let __testfixtures_url = ::std::env::var(#db)
+                    .unwrap_or_else(|_| panic!("testfixtures: {} is not set", #db));
+                let __testfixtures_pool = #pool::new(&__testfixtures_url)
+                    .await
+                    .unwrap_or_else(|err| panic!("testfixtures: {}", err));
+                let __testfixtures_loader = #loader::new(|cfg| {
+                    cfg.location(::testfixtures::export::chrono::Utc);
+                    cfg.database(__testfixtures_pool.clone());
+                    #skip_test_database_check
+                    cfg.paths(vec![#(#fixtures),*]);
+                })
+                .await
+                .unwrap_or_else(|err| panic!("{}", err));
+                __testfixtures_loader
+                    .load()
+                    .await
+                    .unwrap_or_else(|err| panic!("{}", err));
+                #bind_pool
+                #block
+            }
+            ::testfixtures::export::block_on(__testfixtures_test())
+        }
+    })
+}
@@ -1,11 +1,10 @@
-use chrono::{prelude::*, NaiveDate, Utc};
+use chrono::{prelude::*, NaiveDate, NaiveTime, Utc};
 use sqlx::{cursor::Cursor, mysql::MySqlQueryAs, MySqlPool, Row};
 use std::env;
 use std::fs::File;
 use std::io::Write;
-use std::panic;
 use tempfile::tempdir;
-use testfixtures::MySqlLoader;
+use testfixtures::{Cleanup, Dumper, Error, MySqlLoader, MySqlTestDatabase};
 
 #[cfg_attr(feature = "runtime-async-std", async_std::test)]
 #[cfg_attr(feature = "runtime-tokio", tokio::test)]
@@ -58,9 +57,15 @@ async fn it_returns_ok() -> anyhow::Result<()> {
     let created_at: NaiveDateTime = row.get("created_at");
     assert_eq!(id, 1);
     assert_eq!(description, "fizz");
-    assert_eq!(done, true);
+    assert!(done);
     assert_eq!(progress, 10.5);
-    assert_eq!(created_at, NaiveDate::from_ymd(2020, 1, 1).and_hms(1, 1, 1));
+    assert_eq!(
+        created_at,
+        NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(1, 1, 1)
+            .unwrap()
+    );
 
     let row = cursor.next().await?.unwrap();
     let id: u16 = row.get("id");
@@ -70,9 +75,15 @@ async fn it_returns_ok() -> anyhow::Result<()> {
     let created_at: NaiveDateTime = row.get("created_at");
     assert_eq!(id, 2);
     assert_eq!(description, "buzz");
-    assert_eq!(done, false);
+    assert!(!done);
     assert_eq!(progress, 30.0);
-    assert_eq!(created_at, NaiveDate::from_ymd(2020, 1, 1).and_hms(2, 2, 2));
+    assert_eq!(
+        created_at,
+        NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(2, 2, 2)
+            .unwrap()
+    );
 
     let row = cursor.next().await?.unwrap();
     let id: u16 = row.get("id");
@@ -81,7 +92,7 @@ async fn it_returns_ok() -> anyhow::Result<()> {
     let progress: f32 = row.get("progress");
     assert_eq!(id, 3);
     assert_eq!(description, "buzz");
-    assert_eq!(done, false);
+    assert!(!done);
     assert_eq!(progress, 25.0);
     // TODO: check if created_at is the expected value.
     Ok(())
@@ -114,14 +125,31 @@ async fn it_returns_database_check_error() -> anyhow::Result<()> {
     let result = loader.load().await;
     assert!(result.is_err());
     if let Err(err) = result {
+        assert!(matches!(err, Error::NotATestDatabase { .. }));
         assert_eq!(
             err.to_string(),
-            r#"testfixtures: 'fizz' does not appear to be a test database"#
+            r#"testfixtures: 'fizz' does not appear to be a test database: name does not end with test"#
         );
     }
     Ok(())
 }
 
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_does_not_support_require_local_host() -> anyhow::Result<()> {
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.require_local_host();
+        cfg.paths(vec!["tests/fixtures/tags.yml"]);
+    })
+    .await?;
+    let result = loader.load().await;
+    assert!(matches!(result, Err(Error::Unsupported { .. })));
+    Ok(())
+}
+
 #[cfg_attr(feature = "runtime-async-std", async_std::test)]
 #[cfg_attr(feature = "runtime-tokio", tokio::test)]
 async fn it_returns_transaction_error() -> anyhow::Result<()> {
@@ -136,7 +164,8 @@ async fn it_returns_transaction_error() -> anyhow::Result<()> {
           description: fizz
           done: 1
           progress: 10.5
-          updated_at: 2020/01/01 01:01:01"#
+        - id: 1
+          description: buzz"#
     )
     .unwrap();
 
@@ -150,10 +179,379 @@ async fn it_returns_transaction_error() -> anyhow::Result<()> {
     let result = loader.load().await;
     assert!(result.is_err());
     if let Err(err) = result {
+        assert!(matches!(
+            err,
+            Error::Query {
+                record_index: Some(1),
+                ..
+            }
+        ));
         assert_eq!(
             err.to_string(),
-            r#"testfixtures: Unknown column 'updated_at' in 'field list'"#
+            r#"testfixtures: failed to insert record 1 into todos: Duplicate entry '1' for key 'PRIMARY'"#
         );
     }
     Ok(())
 }
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_keeps_exact_decimals() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("invoices.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          amount: 19.99
+        - id: 2
+          amount: DECIMAL=1234567890.12"#
+    )?;
+
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let amounts: Vec<(String,)> =
+        sqlx::query_as("SELECT CAST(amount AS CHAR) FROM invoices ORDER BY id")
+            .fetch_all(&pool_for_query)
+            .await?;
+    assert_eq!(
+        amounts,
+        vec![("19.99".to_string(),), ("1234567890.12".to_string(),)]
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_loads_schema_qualified_tables() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("billing.invoices.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          amount: 10.00"#
+    )?;
+
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let rec: (i64,) = sqlx::query_as("SELECT count(*) FROM billing.invoices")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, 1);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_cleans_tables_by_strategy() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let projects_path = dir.path().join("projects.yml");
+    let mut file = File::create(&projects_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          name: fizz"#
+    )?;
+    let tasks_path = dir.path().join("tasks.yml");
+    let mut file = File::create(&tasks_path)?;
+    writeln!(
+        file,
+        r#"
+        - project_id: 1
+          number: 1
+          title: buzz
+        - project_id: 1
+          number: 2
+          title: fizzbuzz"#
+    )?;
+
+    // Foreign key checks are off while tables are truncated.
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.cleanup(Cleanup::Truncate);
+        cfg.paths(vec![
+            projects_path.to_str().unwrap(),
+            tasks_path.to_str().unwrap(),
+        ]);
+    })
+    .await?;
+    loader.load().await?;
+    sqlx::query("INSERT INTO tasks (project_id, number, title) VALUES (1, 3, 'kept')")
+        .execute(&pool_for_query)
+        .await?;
+    sqlx::query("UPDATE tasks SET title = 'changed'")
+        .execute(&pool_for_query)
+        .await?;
+
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.cleanup(Cleanup::DeleteWhereFixtureKeys);
+        cfg.paths(vec![tasks_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let titles: Vec<(String,)> = sqlx::query_as("SELECT title FROM tasks ORDER BY number")
+        .fetch_all(&pool_for_query)
+        .await?;
+    assert_eq!(
+        titles,
+        vec![
+            ("buzz".to_string(),),
+            ("fizzbuzz".to_string(),),
+            ("changed".to_string(),)
+        ]
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_resets_auto_increment() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("notes.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          body: fizz
+        - id: 2
+          body: buzz
+        - id: 3
+          body: fizzbuzz"#
+    )?;
+
+    for _ in 0..2 {
+        let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+        let pool_for_query = pool.clone();
+        let loader = MySqlLoader::new(|cfg| {
+            cfg.location(Utc);
+            cfg.database(pool);
+            cfg.paths(vec![file_path.to_str().unwrap()]);
+        })
+        .await?;
+        loader.load().await?;
+        sqlx::query("INSERT INTO notes (body) VALUES ('new')")
+            .execute(&pool_for_query)
+            .await?;
+        let rec: (u64,) = sqlx::query_as("SELECT id FROM notes WHERE body = 'new'")
+            .fetch_one(&pool_for_query)
+            .await?;
+        assert_eq!(rec.0, 4);
+    }
+
+    // A row which is inserted and deleted leaves the rows as they were, but not the counter.
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_unchanged();
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    for _ in 0..2 {
+        loader.load().await?;
+        sqlx::query("INSERT INTO notes (body) VALUES ('new')")
+            .execute(&pool_for_query)
+            .await?;
+        let rec: (u64,) = sqlx::query_as("SELECT id FROM notes WHERE body = 'new'")
+            .fetch_one(&pool_for_query)
+            .await?;
+        assert_eq!(rec.0, 4);
+        sqlx::query("DELETE FROM notes WHERE id = 4")
+            .execute(&pool_for_query)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_dumps_tables() -> anyhow::Result<()> {
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    sqlx::query("TRUNCATE TABLE dumps").execute(&pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO dumps (id, amount, ratio, done, created_at, due_on, payload, note)
+        VALUES
+            (1, 19.99, 0.5, true, '2020-01-01 01:01:01', '2020-01-02', '{"name": "it''s"}', 'fizz'),
+            (2, NULL, NULL, NULL, NULL, NULL, NULL, NULL)"#,
+    )
+    .execute(&pool)
+    .await?;
+    let select = r#"
+        SELECT CONCAT_WS('|', id, amount, ratio, done, created_at, due_on, payload, note)
+        FROM dumps ORDER BY id"#;
+    let before: Vec<(String,)> = sqlx::query_as(select).fetch_all(&pool).await?;
+
+    let dir = tempdir()?;
+    Dumper::dump(&pool, vec!["dumps"], dir.path().to_str().unwrap()).await?;
+    let file_path = dir.path().join("dumps.yml");
+    assert!(std::fs::read_to_string(&file_path)?.contains("2020-01-01 01:01:01"));
+
+    sqlx::query("TRUNCATE TABLE dumps").execute(&pool).await?;
+    let pool_for_query = pool.clone();
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let after: Vec<(String,)> = sqlx::query_as(select).fetch_all(&pool_for_query).await?;
+    assert_eq!(after, before);
+    Ok(())
+}
+
+#[testfixtures::test(fixtures = ["tests/fixtures/tags.yml"], db = "TEST_DB_URL")]
+async fn it_loads_fixtures_with_the_test_macro(pool: MySqlPool) -> anyhow::Result<()> {
+    let rows: Vec<(u64, String)> = sqlx::query_as("SELECT id, name FROM tags ORDER BY id")
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(rows, vec![(1, "fizz".to_string()), (2, "buzz".to_string())]);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_creates_test_databases() -> anyhow::Result<()> {
+    let url = env::var("TEST_DB_URL")?;
+    let first = MySqlTestDatabase::new(&url, |cfg| {
+        cfg.location(Utc);
+        cfg.paths(vec!["tests/fixtures/tags.yml"]);
+    })
+    .await?;
+    let second = MySqlTestDatabase::new(&url, |cfg| {
+        cfg.location(Utc);
+        cfg.paths(vec!["tests/fixtures/tags.yml"]);
+    })
+    .await?;
+    assert_ne!(first.name(), second.name());
+
+    sqlx::query("DELETE FROM tags WHERE id = 1")
+        .execute(first.pool())
+        .await?;
+    let rec: (i64,) = sqlx::query_as("SELECT count(*) FROM tags")
+        .fetch_one(first.pool())
+        .await?;
+    assert_eq!(rec.0, 1);
+    let rec: (i64,) = sqlx::query_as("SELECT count(*) FROM tags")
+        .fetch_one(second.pool())
+        .await?;
+    assert_eq!(rec.0, 2);
+
+    let pool = MySqlPool::new(&url).await?;
+    let names = vec![first.name().to_string(), second.name().to_string()];
+    first.close().await?;
+    // A guard which is not closed drops its database when it is dropped.
+    drop(second);
+    for name in names {
+        let rec: (i64,) = sqlx::query_as(
+            "SELECT count(*) FROM information_schema.schemata WHERE schema_name = ?",
+        )
+        .bind(name)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(rec.0, 0);
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_binds_values_by_column_types() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("typed_values.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          label: 2020/01/01 01:01
+          level: 3
+          due_on: 2020-01-31
+          starts_at: 09:30
+          payload:
+            tags: [fizz, buzz]
+          data: fizz
+          priority: high"#
+    )?;
+
+    let pool = MySqlPool::new(&env::var("TEST_DB_URL")?).await?;
+    let loader = MySqlLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool.clone());
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+
+    type TypedValue = (String, i16, NaiveDate, NaiveTime, String, Vec<u8>, String);
+    let rows: Vec<TypedValue> = sqlx::query_as(
+        "SELECT label, level, due_on, starts_at, CAST(payload AS CHAR), data, CAST(priority AS CHAR) FROM typed_values",
+    )
+    .fetch_all(&pool)
+    .await?;
+    assert_eq!(
+        rows,
+        vec![(
+            "2020/01/01 01:01".to_string(),
+            3,
+            NaiveDate::from_ymd_opt(2020, 1, 31).unwrap(),
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            r#"{"tags": ["fizz", "buzz"]}"#.to_string(),
+            b"fizz".to_vec(),
+            "high".to_string()
+        )]
+    );
+
+    let file_path = dir.path().join("typed_values.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          priority: urgent"#
+    )?;
+    let result = MySqlLoader::<Utc, Utc>::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await;
+    match result {
+        Err(err @ Error::InvalidRecord { .. }) => assert_eq!(
+            err.to_string(),
+            format!(
+                "testfixtures: record 0 of typed_values in {}: column priority can not store 'urgent': not one of low, high",
+                file_path.to_str().unwrap()
+            )
+        ),
+        _ => panic!("expected an invalid value error"),
+    }
+    Ok(())
+}
@@ -0,0 +1,677 @@
+use chrono::{prelude::*, NaiveDate, NaiveTime, Utc};
+use sqlx::{cursor::Cursor, postgres::PgQueryAs, PgPool, Row};
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use tempfile::tempdir;
+use testfixtures::{Cleanup, Dumper, Error, PostgresLoader, PostgresTestDatabase};
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_ok() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: true
+          progress: 10.5
+          created_at: 2020/01/01 01:01:01
+        - id: 2
+          description: buzz
+          done: false
+          progress: 30.0
+          created_at: 2020/01/01 02:02:02
+        - id: 3
+          description: buzz
+          done: false
+          progress: 25.0
+          created_at: RAW=NOW()"#
+    )
+    .unwrap();
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    assert!(loader.load().await.is_ok());
+    let rec: (i64,) = sqlx::query_as("SELECT count(*) from todos")
+        .fetch_one(&pool_for_query.clone())
+        .await?;
+    assert_eq!(rec.0, 3);
+    let mut cursor =
+        sqlx::query("SELECT id, description, done, progress, created_at FROM todos ORDER BY id")
+            .fetch(&pool_for_query);
+    let row = cursor.next().await?.unwrap();
+    let id: i64 = row.get("id");
+    let description: String = row.get("description");
+    let done: bool = row.get("done");
+    let progress: f32 = row.get("progress");
+    let created_at: NaiveDateTime = row.get("created_at");
+    assert_eq!(id, 1);
+    assert_eq!(description, "fizz");
+    assert!(done);
+    assert_eq!(progress, 10.5);
+    assert_eq!(
+        created_at,
+        NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(1, 1, 1)
+            .unwrap()
+    );
+
+    let row = cursor.next().await?.unwrap();
+    let id: i64 = row.get("id");
+    let description: String = row.get("description");
+    let done: bool = row.get("done");
+    let progress: f32 = row.get("progress");
+    let created_at: NaiveDateTime = row.get("created_at");
+    assert_eq!(id, 2);
+    assert_eq!(description, "buzz");
+    assert!(!done);
+    assert_eq!(progress, 30.0);
+    assert_eq!(
+        created_at,
+        NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(2, 2, 2)
+            .unwrap()
+    );
+
+    let row = cursor.next().await?.unwrap();
+    let id: i64 = row.get("id");
+    let description: String = row.get("description");
+    let done: bool = row.get("done");
+    let progress: f32 = row.get("progress");
+    assert_eq!(id, 3);
+    assert_eq!(description, "buzz");
+    assert!(!done);
+    assert_eq!(progress, 25.0);
+    // TODO: check if created_at is the expected value.
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_database_check_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: true
+          progress: 10.5"#
+    )
+    .unwrap();
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL_FOR_DB_CHECK")?).await?;
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    let result = loader.load().await;
+    assert!(result.is_err());
+    if let Err(err) = result {
+        assert!(matches!(err, Error::NotATestDatabase { .. }));
+        assert_eq!(
+            err.to_string(),
+            r#"testfixtures: 'fizz' does not appear to be a test database: name does not end with test"#
+        );
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_transaction_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: true
+          progress: 10.5
+        - id: 1
+          description: buzz"#
+    )
+    .unwrap();
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    let result = loader.load().await;
+    assert!(result.is_err());
+    if let Err(err) = result {
+        assert!(matches!(
+            err,
+            Error::Query {
+                record_index: Some(1),
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            r#"testfixtures: failed to insert record 1 into todos: duplicate key value violates unique constraint "todos_pkey""#
+        );
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_keeps_exact_decimals() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("invoices.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          amount: 19.99
+        - id: 2
+          amount: DECIMAL=1234567890.12"#
+    )?;
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let amounts: Vec<(String,)> = sqlx::query_as("SELECT amount::text FROM invoices ORDER BY id")
+        .fetch_all(&pool_for_query)
+        .await?;
+    assert_eq!(
+        amounts,
+        vec![("19.99".to_string(),), ("1234567890.12".to_string(),)]
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_loads_schema_qualified_tables() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("billing.invoices.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          amount: 10.00"#
+    )?;
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let rec: (i64,) = sqlx::query_as("SELECT count(*) FROM billing.invoices")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, 1);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_cleans_tables_by_strategy() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let projects_path = dir.path().join("projects.yml");
+    let mut file = File::create(&projects_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          name: fizz"#
+    )?;
+    let tasks_path = dir.path().join("tasks.yml");
+    let mut file = File::create(&tasks_path)?;
+    writeln!(
+        file,
+        r#"
+        - project_id: 1
+          number: 1
+          title: buzz
+        - project_id: 1
+          number: 2
+          title: fizzbuzz"#
+    )?;
+
+    // Tasks reference projects, which can be truncated together.
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.cleanup(Cleanup::Truncate);
+        cfg.paths(vec![
+            projects_path.to_str().unwrap(),
+            tasks_path.to_str().unwrap(),
+        ]);
+    })
+    .await?;
+    loader.load().await?;
+    sqlx::query("INSERT INTO tasks (project_id, number, title) VALUES (1, 3, 'kept')")
+        .execute(&pool_for_query)
+        .await?;
+    sqlx::query("UPDATE tasks SET title = 'changed'")
+        .execute(&pool_for_query)
+        .await?;
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.cleanup(Cleanup::DeleteWhereFixtureKeys);
+        cfg.paths(vec![tasks_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let titles: Vec<(String,)> = sqlx::query_as("SELECT title FROM tasks ORDER BY number")
+        .fetch_all(&pool_for_query)
+        .await?;
+    assert_eq!(
+        titles,
+        vec![
+            ("buzz".to_string(),),
+            ("fizzbuzz".to_string(),),
+            ("changed".to_string(),)
+        ]
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_resets_sequences() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("notes.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          body: fizz
+        - id: 2
+          body: buzz
+        - id: 3
+          body: fizzbuzz"#
+    )?;
+
+    for _ in 0..2 {
+        let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+        let pool_for_query = pool.clone();
+        let loader = PostgresLoader::new(|cfg| {
+            cfg.location(Utc);
+            cfg.database(pool);
+            cfg.paths(vec![file_path.to_str().unwrap()]);
+        })
+        .await?;
+        loader.load().await?;
+        let rec: (i64,) = sqlx::query_as("INSERT INTO notes (body) VALUES ('new') RETURNING id")
+            .fetch_one(&pool_for_query)
+            .await?;
+        assert_eq!(rec.0, 4);
+    }
+
+    // A row which is inserted and deleted leaves the rows as they were, but not the sequence.
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let pool_for_query = pool.clone();
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_unchanged();
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    for _ in 0..2 {
+        loader.load().await?;
+        let rec: (i64,) = sqlx::query_as("INSERT INTO notes (body) VALUES ('new') RETURNING id")
+            .fetch_one(&pool_for_query)
+            .await?;
+        assert_eq!(rec.0, 4);
+        sqlx::query("DELETE FROM notes WHERE id = 4")
+            .execute(&pool_for_query)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_dumps_tables() -> anyhow::Result<()> {
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    sqlx::query("TRUNCATE TABLE dumps").execute(&pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO dumps (id, amount, ratio, done, created_at, due_on, token, payload, note)
+        VALUES
+            (1, 19.99, 'Infinity', true, '2020-01-01 01:01:01', '2020-01-02',
+                'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', '{"name": "it''s"}', 'fizz'),
+            (2, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL)"#,
+    )
+    .execute(&pool)
+    .await?;
+    let select = "SELECT id, amount::text, ratio, done, created_at, due_on::text, token::text, payload::text, note FROM dumps ORDER BY id";
+    type Dump = (
+        i64,
+        Option<String>,
+        Option<f64>,
+        Option<bool>,
+        Option<NaiveDateTime>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    );
+    let before: Vec<Dump> = sqlx::query_as(select).fetch_all(&pool).await?;
+
+    let dir = tempdir()?;
+    Dumper::dump(&pool, vec!["dumps"], dir.path().to_str().unwrap()).await?;
+    let file_path = dir.path().join("dumps.yml");
+    assert!(std::fs::read_to_string(&file_path)?.contains("2020-01-01 01:01:01"));
+
+    sqlx::query("TRUNCATE TABLE dumps").execute(&pool).await?;
+    let pool_for_query = pool.clone();
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let after: Vec<Dump> = sqlx::query_as(select).fetch_all(&pool_for_query).await?;
+    assert_eq!(after, before);
+    Ok(())
+}
+
+#[testfixtures::test(
+    fixtures = ["tests/fixtures/tags.yml"],
+    db = "TEST_PG_URL",
+    backend = "postgresql"
+)]
+async fn it_loads_fixtures_with_the_test_macro(pool: PgPool) -> anyhow::Result<()> {
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, name FROM tags ORDER BY id")
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(rows, vec![(1, "fizz".to_string()), (2, "buzz".to_string())]);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_creates_test_databases() -> anyhow::Result<()> {
+    let url = env::var("TEST_PG_URL")?;
+    let server = url.rsplit_once('/').unwrap().0;
+    let template_url = format!("{}/testfixtures_template", server);
+
+    // A template database can not have other connections while it is copied.
+    let pool = PgPool::new(&format!("{}/postgres", server)).await?;
+    sqlx::query("DROP DATABASE IF EXISTS testfixtures_template")
+        .execute(&pool)
+        .await?;
+    sqlx::query("CREATE DATABASE testfixtures_template")
+        .execute(&pool)
+        .await?;
+    let template = PgPool::new(&template_url).await?;
+    sqlx::query("CREATE TABLE tags (id BIGSERIAL PRIMARY KEY NOT NULL, name TEXT NOT NULL)")
+        .execute(&template)
+        .await?;
+    template.close().await;
+
+    let first = PostgresTestDatabase::new(&template_url, |cfg| {
+        cfg.location(Utc);
+        cfg.paths(vec!["tests/fixtures/tags.yml"]);
+    })
+    .await?;
+    let second = PostgresTestDatabase::new(&template_url, |cfg| {
+        cfg.location(Utc);
+        cfg.paths(vec!["tests/fixtures/tags.yml"]);
+    })
+    .await?;
+    assert!(first.name().starts_with("testfixtures_template_"));
+    assert_ne!(first.name(), second.name());
+
+    sqlx::query("DELETE FROM tags WHERE id = 1")
+        .execute(first.pool())
+        .await?;
+    let rec: (i64,) = sqlx::query_as("SELECT count(*) FROM tags")
+        .fetch_one(first.pool())
+        .await?;
+    assert_eq!(rec.0, 1);
+    let rec: (i64,) = sqlx::query_as("SELECT count(*) FROM tags")
+        .fetch_one(second.pool())
+        .await?;
+    assert_eq!(rec.0, 2);
+
+    let names = vec![first.name().to_string(), second.name().to_string()];
+    first.close().await?;
+    // A guard which is not closed drops its database when it is dropped.
+    drop(second);
+    for name in names {
+        let rec: (i64,) = sqlx::query_as("SELECT count(*) FROM pg_database WHERE datname = $1")
+            .bind(name)
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(rec.0, 0);
+    }
+
+    sqlx::query("DROP DATABASE testfixtures_template")
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_loads_fixtures_into_a_transaction() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("projects.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          name: fizz"#
+    )?;
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool.clone());
+        cfg.cleanup(Cleanup::None);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM tasks").execute(&mut tx).await?;
+    sqlx::query("DELETE FROM projects").execute(&mut tx).await?;
+    loader.load_into(&mut tx).await?;
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, name FROM projects")
+        .fetch_all(&mut tx)
+        .await?;
+    assert_eq!(rows, vec![(1, "fizz".to_string())]);
+    tx.rollback().await?;
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_binds_values_by_column_types() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("typed_values.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          label: 2020/01/01 01:01
+          level: 3
+          due_on: 2020-01-31
+          starts_at: 09:30
+          payload:
+            tags: [fizz, buzz]
+          data: fizz
+          priority: high
+        - id: 2
+          label: 10
+          level: ~
+          due_on: ~
+          starts_at: ~
+          payload: '[1, 2]'
+          data: ~
+          priority: low"#
+    )?;
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let loader = PostgresLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool.clone());
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+
+    type TypedValue = (
+        String,
+        Option<i16>,
+        Option<NaiveDate>,
+        Option<NaiveTime>,
+        String,
+        Option<Vec<u8>>,
+        String,
+    );
+    let rows: Vec<TypedValue> = sqlx::query_as(
+        "SELECT label, level, due_on, starts_at, payload::jsonb::text, data, priority::text FROM typed_values ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await?;
+    assert_eq!(
+        rows,
+        vec![
+            (
+                "2020/01/01 01:01".to_string(),
+                Some(3),
+                NaiveDate::from_ymd_opt(2020, 1, 31),
+                NaiveTime::from_hms_opt(9, 30, 0),
+                r#"{"tags": ["fizz", "buzz"]}"#.to_string(),
+                Some(b"fizz".to_vec()),
+                "high".to_string()
+            ),
+            (
+                "10".to_string(),
+                None,
+                None,
+                None,
+                "[1, 2]".to_string(),
+                None,
+                "low".to_string()
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_invalid_value_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("typed_values.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          level: 40000
+          label: fizz"#
+    )?;
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let result = PostgresLoader::<Utc, Utc>::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await;
+    match result {
+        Err(err @ Error::InvalidRecord { .. }) => assert_eq!(
+            err.to_string(),
+            format!(
+                "testfixtures: record 0 of typed_values in {}: column level can not store '40000': not an integer from -32768 to 32767",
+                file_path.to_str().unwrap()
+            )
+        ),
+        _ => panic!("expected an invalid value error"),
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_validates_fixtures_against_the_schema() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("typed_values.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          label: a label longer than thirty-two characters
+          priority: urgent
+        - id: 2
+          colour: red"#
+    )?;
+
+    let pool = PgPool::new(&env::var("TEST_PG_URL")?).await?;
+    let result = PostgresLoader::<Utc, Utc>::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await;
+    let errors = match result {
+        Err(Error::Fixtures(errors)) => errors,
+        _ => panic!("expected fixture errors"),
+    };
+    let path = file_path.to_str().unwrap();
+    assert_eq!(
+        errors.iter().map(|x| x.to_string()).collect::<Vec<String>>(),
+        vec![
+            format!("testfixtures: record 0 of typed_values in {}: column label can not store 'a label longer than thirty-two characters': longer than 32 characters", path),
+            format!("testfixtures: record 0 of typed_values in {}: column priority can not store 'urgent': not one of low, high", path),
+            format!("testfixtures: record 1 of typed_values in {}: column colour does not exist", path),
+        ]
+    );
+    Ok(())
+}
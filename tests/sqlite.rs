@@ -0,0 +1,804 @@
+use chrono::{prelude::*, NaiveDate, Utc};
+use sqlx::{cursor::Cursor, sqlite::SqliteQueryAs, Row, SqlitePool};
+use std::fs::File;
+use std::io::Write;
+use tempfile::tempdir;
+use testfixtures::{Cleanup, Dumper, Error, SqliteLoader};
+
+async fn create_pool(url: &str) -> anyhow::Result<SqlitePool> {
+    let pool = SqlitePool::builder().max_size(1).build(url).await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE todos (
+            id INTEGER PRIMARY KEY NOT NULL,
+            description TEXT NOT NULL,
+            done BOOLEAN NOT NULL DEFAULT FALSE,
+            progress REAL,
+            created_at DATETIME
+        )"#,
+    )
+    .execute(&pool)
+    .await?;
+    Ok(pool)
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_ok() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: true
+          progress: 10.5
+          created_at: 2020/01/01 01:01:01
+        - id: 2
+          description: buzz
+          done: false
+          progress: 30.0
+          created_at: 2020/01/01 02:02:02
+        - id: 3
+          description: buzz
+          done: false
+          progress: 25.0
+          created_at: RAW=datetime('now')"#
+    )
+    .unwrap();
+
+    let database_path = dir.path().join("test.db");
+    let pool =
+        create_pool(format!("sqlite://{}", database_path.to_str().unwrap()).as_str()).await?;
+    let pool_for_query = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    assert!(loader.load().await.is_ok());
+    let rec: (i32,) = sqlx::query_as("SELECT count(*) from todos")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, 3);
+    let mut cursor =
+        sqlx::query("SELECT id, description, done, progress, created_at FROM todos ORDER BY id")
+            .fetch(&pool_for_query);
+    let row = cursor.next().await?.unwrap();
+    let id: i32 = row.get("id");
+    let description: String = row.get("description");
+    let done: bool = row.get("done");
+    let progress: f32 = row.get("progress");
+    let created_at: String = row.get("created_at");
+    assert_eq!(id, 1);
+    assert_eq!(description, "fizz");
+    assert!(done);
+    assert_eq!(progress, 10.5);
+    assert_eq!(
+        NaiveDateTime::parse_from_str(created_at.as_str(), "%Y-%m-%d %H:%M:%S")?,
+        NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(1, 1, 1)
+            .unwrap()
+    );
+
+    let row = cursor.next().await?.unwrap();
+    let id: i32 = row.get("id");
+    let description: String = row.get("description");
+    let done: bool = row.get("done");
+    let progress: f32 = row.get("progress");
+    let created_at: String = row.get("created_at");
+    assert_eq!(id, 2);
+    assert_eq!(description, "buzz");
+    assert!(!done);
+    assert_eq!(progress, 30.0);
+    assert_eq!(
+        NaiveDateTime::parse_from_str(created_at.as_str(), "%Y-%m-%d %H:%M:%S")?,
+        NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(2, 2, 2)
+            .unwrap()
+    );
+
+    let row = cursor.next().await?.unwrap();
+    let id: i32 = row.get("id");
+    let description: String = row.get("description");
+    let done: bool = row.get("done");
+    let progress: f32 = row.get("progress");
+    assert_eq!(id, 3);
+    assert_eq!(description, "buzz");
+    assert!(!done);
+    assert_eq!(progress, 25.0);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_database_check_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: true
+          progress: 10.5"#
+    )
+    .unwrap();
+
+    let database_path = dir.path().join("fizz.db");
+    let pool =
+        create_pool(format!("sqlite://{}", database_path.to_str().unwrap()).as_str()).await?;
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    let result = loader.load().await;
+    assert!(result.is_err());
+    if let Err(err) = result {
+        assert!(matches!(err, Error::NotATestDatabase { .. }));
+        assert_eq!(
+            err.to_string(),
+            r#"testfixtures: 'fizz' does not appear to be a test database: name does not end with test"#
+        );
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_transaction_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: true
+          progress: 10.5
+        - id: 1
+          description: buzz"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    let result = loader.load().await;
+    assert!(result.is_err());
+    if let Err(err) = result {
+        assert!(matches!(
+            err,
+            Error::Query {
+                record_index: Some(1),
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            r#"testfixtures: failed to insert record 1 into todos: UNIQUE constraint failed: todos.id"#
+        );
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_cleans_all_tables() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY NOT NULL)")
+        .execute(&pool)
+        .await?;
+    sqlx::query("INSERT INTO users (id) VALUES (1)")
+        .execute(&pool)
+        .await?;
+    let pool_for_query = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.clean_all_tables();
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    assert!(loader.load().await.is_ok());
+    let rec: (i32,) = sqlx::query_as("SELECT count(*) from users")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, 0);
+    let rec: (i32,) = sqlx::query_as("SELECT count(*) from todos")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, 1);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_missing_fixture_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let missing_file_path = dir.path().join("todos.yml");
+    let pool = create_pool("sqlite:").await?;
+    let result = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.paths(vec![missing_file_path.to_str().unwrap()]);
+    })
+    .await;
+    match result {
+        Err(err @ Error::Io { .. }) => assert!(err.to_string().starts_with(&format!(
+            "testfixtures: failed to read {}: ",
+            missing_file_path.to_str().unwrap()
+        ))),
+        _ => panic!("expected an io error"),
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_loads_multi_table_files() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("scenario_checkout.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        users:
+          - id: 1
+        todos:
+          - id: 1
+            description: fizz
+          - id: 2
+            description: buzz"#
+    )?;
+
+    let pool = create_pool("sqlite:").await?;
+    sqlx::query("CREATE TABLE users (id INTEGER PRIMARY KEY NOT NULL)")
+        .execute(&pool)
+        .await?;
+    sqlx::query("INSERT INTO users (id) VALUES (2)")
+        .execute(&pool)
+        .await?;
+    let pool_for_query = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let users: Vec<(i32,)> = sqlx::query_as("SELECT id FROM users")
+        .fetch_all(&pool_for_query)
+        .await?;
+    assert_eq!(users, vec![(1,)]);
+    let rec: (i32,) = sqlx::query_as("SELECT count(*) from todos")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, 2);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_loads_large_fixtures() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let mut file = File::create(&file_path)?;
+    for id in 1..=5000 {
+        writeln!(
+            file,
+            "- id: {}\n  description: todo {}\n  done: true",
+            id, id
+        )?;
+    }
+
+    let pool = create_pool("sqlite:").await?;
+    let pool_for_query = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    assert!(loader.fixture_files[0].insert_sqls.len() > 1);
+    loader.load().await?;
+    let rec: (i32, i32) = sqlx::query_as("SELECT count(*), max(id) from todos")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec, (5000, 5000));
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_quotes_identifiers() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("order.yml");
+    let mut file = File::create(&file_path)?;
+    writeln!(
+        file,
+        r#"
+        - key: 1
+          group: fizz"#
+    )?;
+
+    let pool = create_pool("sqlite:").await?;
+    sqlx::query(r#"CREATE TABLE "order" ("key" INTEGER PRIMARY KEY NOT NULL, "group" TEXT)"#)
+        .execute(&pool)
+        .await?;
+    let pool_for_query = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool.clone());
+        cfg.skip_test_database_check();
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+    let rec: (i32, String) = sqlx::query_as(r#"SELECT "key", "group" FROM "order""#)
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec, (1, "fizz".to_string()));
+
+    let mut file = File::create(&file_path)?;
+    writeln!(file, "- \"\": 1")?;
+    let result = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![file_path.to_str().unwrap()]);
+    })
+    .await;
+    match result {
+        Err(Error::Fixtures(errors)) => {
+            assert!(matches!(errors[0], Error::InvalidRecord { .. }));
+            assert_eq!(
+                errors[1].to_string(),
+                r#"testfixtures: "" can not be used as a table or column name"#
+            );
+        }
+        _ => panic!("expected an invalid identifier error"),
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_cleans_tables_by_strategy() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+        - id: 2
+          description: buzz"#
+    )
+    .unwrap();
+
+    for (cleanup, expected) in [
+        (Cleanup::Delete, vec!["fizz", "buzz"]),
+        (Cleanup::Truncate, vec!["fizz", "buzz"]),
+        (
+            Cleanup::DeleteWhereFixtureKeys,
+            vec!["fizz", "buzz", "kept"],
+        ),
+    ] {
+        let pool = create_pool("sqlite:").await?;
+        sqlx::query("INSERT INTO todos (id, description) VALUES (1, 'old'), (3, 'kept')")
+            .execute(&pool)
+            .await?;
+        let pool_for_query = pool.clone();
+        let loader = SqliteLoader::new(|cfg| {
+            cfg.location(Utc);
+            cfg.database(pool);
+            cfg.skip_test_database_check();
+            cfg.cleanup(cleanup);
+            cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+        })
+        .await?;
+        loader.load().await?;
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT description FROM todos ORDER BY id")
+            .fetch_all(&pool_for_query)
+            .await?;
+        let rows: Vec<String> = rows.into_iter().map(|x| x.0).collect();
+        assert_eq!(rows, expected, "{:?}", cleanup);
+    }
+
+    let pool = create_pool("sqlite:").await?;
+    sqlx::query("INSERT INTO todos (id, description) VALUES (1, 'old')")
+        .execute(&pool)
+        .await?;
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.cleanup(Cleanup::None);
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    match loader.load().await {
+        Err(Error::Query {
+            table,
+            record_index,
+            ..
+        }) => {
+            assert_eq!(table, "todos");
+            assert_eq!(record_index, Some(0));
+        }
+        _ => panic!("expected a duplicate key error"),
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_missing_primary_key_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("tags.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - name: fizz"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    sqlx::query("CREATE TABLE tags (name TEXT NOT NULL)")
+        .execute(&pool)
+        .await?;
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.cleanup(Cleanup::DeleteWhereFixtureKeys);
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    match loader.load().await {
+        Err(Error::MissingPrimaryKey { table }) => assert_eq!(table, "tags"),
+        _ => panic!("expected a missing primary key error"),
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_skips_unchanged_fixtures() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+        - id: 2
+          description: buzz"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    let pool_for_query = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.skip_unchanged();
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+
+    // The pool has a single connection, so total_changes() counts the rows written by the loader.
+    let changes: (i32,) = sqlx::query_as("SELECT total_changes()")
+        .fetch_one(&pool_for_query)
+        .await?;
+    loader.load().await?;
+    let rec: (i32,) = sqlx::query_as("SELECT total_changes()")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, changes.0);
+
+    sqlx::query("UPDATE todos SET description = 'changed' WHERE id = 1")
+        .execute(&pool_for_query)
+        .await?;
+    loader.load().await?;
+    let rec: (String,) = sqlx::query_as("SELECT description FROM todos WHERE id = 1")
+        .fetch_one(&pool_for_query)
+        .await?;
+    assert_eq!(rec.0, "fizz");
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_dumps_tables() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: true
+          progress: 10.5
+          created_at: 2020/01/01 01:01:01
+        - id: 2
+          description: buzz
+          done: false
+          progress: null
+          created_at: null"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    let pool_for_dump = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+
+    let dump_dir = tempdir()?;
+    Dumper::dump(
+        &pool_for_dump,
+        vec!["todos"],
+        dump_dir.path().to_str().unwrap(),
+    )
+    .await?;
+    let dump_path = dump_dir.path().join("todos.yml");
+    assert!(std::fs::read_to_string(&dump_path)?.contains("2020-01-01 01:01:01"));
+
+    let pool = create_pool("sqlite:").await?;
+    let pool_for_query = pool.clone();
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![dump_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+
+    type Todo = (i32, String, bool, Option<f64>, Option<String>);
+    let rows: Vec<Todo> =
+        sqlx::query_as("SELECT id, description, done, progress, created_at FROM todos ORDER BY id")
+            .fetch_all(&pool_for_query)
+            .await?;
+    assert_eq!(
+        rows,
+        vec![
+            (
+                1,
+                "fizz".to_string(),
+                true,
+                Some(10.5),
+                Some("2020-01-01 01:01:01".to_string())
+            ),
+            (2, "buzz".to_string(), false, None, None),
+        ]
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_loads_fixtures_into_a_transaction() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+        - id: 2
+          description: buzz"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    sqlx::query("INSERT INTO todos (id, description) VALUES (3, 'kept')")
+        .execute(&pool)
+        .await?;
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool.clone());
+        cfg.skip_test_database_check();
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    loader.load_into(&mut tx).await?;
+    let rows: Vec<(i32, String)> = sqlx::query_as("SELECT id, description FROM todos ORDER BY id")
+        .fetch_all(&mut tx)
+        .await?;
+    assert_eq!(rows, vec![(1, "fizz".to_string()), (2, "buzz".to_string())]);
+    tx.rollback().await?;
+
+    let rows: Vec<(i32, String)> = sqlx::query_as("SELECT id, description FROM todos ORDER BY id")
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(rows, vec![(3, "kept".to_string())]);
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_binds_values_by_column_types() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: 2020/01/01 01:01
+          done: 1
+          progress: 10
+          created_at: 2020-01-31"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    let loader = SqliteLoader::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool.clone());
+        cfg.skip_test_database_check();
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await?;
+    loader.load().await?;
+
+    let rows: Vec<(String, bool, f64, String)> =
+        sqlx::query_as("SELECT description, done, progress, created_at FROM todos")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(
+        rows,
+        vec![(
+            "2020/01/01 01:01".to_string(),
+            true,
+            10.0,
+            "2020-01-31 00:00:00".to_string()
+        )]
+    );
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_returns_invalid_value_error() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let file_path = dir.path().join("todos.yml");
+    let fixture_file_path = file_path.clone();
+    let mut file = File::create(file_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: maybe"#
+    )
+    .unwrap();
+
+    let pool = create_pool("sqlite:").await?;
+    let result = SqliteLoader::<Utc, Utc>::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![fixture_file_path.to_str().unwrap()]);
+    })
+    .await;
+    match result {
+        Err(err @ Error::InvalidRecord { .. }) => assert_eq!(
+            err.to_string(),
+            format!(
+                "testfixtures: record 0 of todos in {}: column done can not store 'maybe': not a boolean",
+                fixture_file_path.to_str().unwrap()
+            )
+        ),
+        _ => panic!("expected an invalid value error"),
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "runtime-async-std", async_std::test)]
+#[cfg_attr(feature = "runtime-tokio", tokio::test)]
+async fn it_validates_fixtures_against_the_schema() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let todos_path = dir.path().join("todos.yml");
+    let mut file = File::create(&todos_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1
+          description: fizz
+          done: ~
+        - id: 2
+          title: buzz
+          progress: fast"#
+    )?;
+    let users_path = dir.path().join("users.yml");
+    let mut file = File::create(&users_path)?;
+    writeln!(
+        file,
+        r#"
+        - id: 1"#
+    )?;
+
+    let pool = create_pool("sqlite:").await?;
+    let result = SqliteLoader::<Utc, Utc>::new(|cfg| {
+        cfg.location(Utc);
+        cfg.database(pool);
+        cfg.skip_test_database_check();
+        cfg.paths(vec![
+            todos_path.to_str().unwrap(),
+            users_path.to_str().unwrap(),
+        ]);
+    })
+    .await;
+    let errors = match result {
+        Err(Error::Fixtures(errors)) => errors,
+        _ => panic!("expected fixture errors"),
+    };
+    let todos_path = todos_path.to_str().unwrap();
+    assert_eq!(
+        errors.iter().map(|x| x.to_string()).collect::<Vec<String>>(),
+        vec![
+            format!("testfixtures: record 0 of todos in {}: column done can not store 'null': the column is NOT NULL", todos_path),
+            format!("testfixtures: record 1 of todos in {}: column progress can not store 'fast': not a number", todos_path),
+            format!("testfixtures: record 1 of todos in {}: column title does not exist", todos_path),
+            format!("testfixtures: record 1 of todos in {}: column description is NOT NULL and has no default, but it is left out", todos_path),
+            format!("testfixtures: users of {} does not exist", users_path.to_str().unwrap()),
+        ]
+    );
+    Ok(())
+}